@@ -0,0 +1,108 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::challenge_resolve::{is_won, parse_level, roll, start_state};
+use crate::models::{BloxorzInput, BloxorzOutput};
+
+/// Levels embedded in the binary, separated by a blank line
+pub static LEVEL_PACK: &str = include_str!("../data/levels.txt");
+
+/// Levels of the embedded pack
+pub fn level_pack() -> Vec<String> {
+    LEVEL_PACK
+        .split("\n\n")
+        .map(|level| level.trim_end_matches('\n').to_string())
+        .filter(|level| !level.trim().is_empty())
+        .collect()
+}
+
+pub fn generate_bloxorz_challenge() -> BloxorzInput {
+    pick_level(&mut rand::thread_rng())
+}
+
+/// Draws one of the levels of the pack
+pub fn pick_level<R: Rng>(rng: &mut R) -> BloxorzInput {
+    let level = level_pack()
+        .choose(rng)
+        .expect("The level pack is not empty")
+        .clone();
+    BloxorzInput { level }
+}
+
+/// Replays the rolls from the start, the block must stay on the level and only stand on the
+/// hole after the last roll
+pub fn validate_bloxorz_challenge(input: &BloxorzInput, output: &BloxorzOutput) -> bool {
+    let level = match parse_level(&input.level) {
+        Ok(level) => level,
+        Err(_) => return false,
+    };
+    let mut state = start_state(&level);
+    for direction in output.path.chars() {
+        if is_won(&level, &state) {
+            return false;
+        }
+        state = match roll(&level, &state, direction) {
+            Some(state) => state,
+            None => return false,
+        };
+    }
+    is_won(&level, &state)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::challenge_resolve::solve_bloxorz;
+
+    use super::*;
+
+    fn input(level: &str) -> BloxorzInput {
+        BloxorzInput {
+            level: level.to_string(),
+        }
+    }
+
+    fn output(path: &str) -> BloxorzOutput {
+        BloxorzOutput {
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_level_pack_is_solvable() {
+        let levels = level_pack();
+        assert_eq!(levels.len(), 5);
+        for level in levels {
+            let input = input(&level);
+            let output = solve_bloxorz(&input).unwrap();
+            assert!(!output.path.is_empty());
+            assert!(validate_bloxorz_challenge(&input, &output));
+        }
+    }
+
+    #[test]
+    fn test_pick_level() {
+        let mut rng = StdRng::seed_from_u64(20);
+        let levels = level_pack();
+        for _ in 0..10 {
+            assert!(levels.contains(&pick_level(&mut rng).level));
+        }
+    }
+
+    #[test]
+    fn test_validate_bloxorz_challenge() {
+        let line = input("SoooooX");
+        assert!(validate_bloxorz_challenge(&line, &output(">>>>")));
+        // Too short, falling off the level, unknown roll, going on past the hole
+        assert!(!validate_bloxorz_challenge(&line, &output(">>>")));
+        assert!(!validate_bloxorz_challenge(&line, &output("^>>>>")));
+        assert!(!validate_bloxorz_challenge(&line, &output(">>x>")));
+        assert!(!validate_bloxorz_challenge(
+            &input("SoooooXoo"),
+            &output(">>>>><")
+        ));
+        assert!(!validate_bloxorz_challenge(&input("S#X"), &output(">")));
+    }
+}
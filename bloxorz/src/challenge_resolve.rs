@@ -0,0 +1,246 @@
+use std::collections::{HashMap, VecDeque};
+
+use log::debug;
+
+use crate::models::{
+    Block, BlockState, BloxorzError, BloxorzInput, BloxorzOutput, Level, Orientation, Tile,
+};
+
+pub static VOID_TOKEN: char = ' ';
+pub static FLOOR_TOKEN: char = 'o';
+pub static START_TOKEN: char = 'S';
+pub static HOLE_TOKEN: char = 'X';
+pub static FRAGILE_TOKEN: char = '~';
+pub static SWITCH_TOKEN: char = '@';
+pub static CLOSED_BRIDGE_TOKEN: char = '=';
+pub static OPEN_BRIDGE_TOKEN: char = '+';
+
+/// Rolls tried from every state, in this order
+pub const MOVES: [char; 4] = ['^', 'v', '<', '>'];
+
+pub fn solve_bloxorz(input: &BloxorzInput) -> Result<BloxorzOutput, BloxorzError> {
+    let level = parse_level(&input.level)?;
+    let path = find_shortest_path(&level).ok_or(BloxorzError::NoPath)?;
+    debug!("Bloxorz solved in {} rolls", path.len());
+    Ok(BloxorzOutput { path })
+}
+
+/// Reads the level line by line, short lines are completed with void
+pub fn parse_level(level: &str) -> Result<Level, BloxorzError> {
+    let lines: Vec<&str> = level.lines().collect();
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let height = lines.len();
+    let mut tiles = vec![Tile::Void; width * height];
+    let (mut start, mut hole) = (None, None);
+    for (y, line) in lines.iter().enumerate() {
+        for (x, token) in line.chars().enumerate() {
+            tiles[y * width + x] = match token {
+                _ if token == VOID_TOKEN => Tile::Void,
+                _ if token == FLOOR_TOKEN => Tile::Floor,
+                _ if token == START_TOKEN => {
+                    start = Some((x, y));
+                    Tile::Floor
+                }
+                _ if token == HOLE_TOKEN => {
+                    hole = Some((x, y));
+                    Tile::Hole
+                }
+                _ if token == FRAGILE_TOKEN => Tile::Fragile,
+                _ if token == SWITCH_TOKEN => Tile::Switch,
+                _ if token == CLOSED_BRIDGE_TOKEN => Tile::Bridge { open: false },
+                _ if token == OPEN_BRIDGE_TOKEN => Tile::Bridge { open: true },
+                _ => return Err(BloxorzError::UnknownTile(token)),
+            };
+        }
+    }
+    Ok(Level {
+        tiles,
+        width,
+        height,
+        start: start.ok_or(BloxorzError::MissingStart)?,
+        hole: hole.ok_or(BloxorzError::MissingHole)?,
+    })
+}
+
+/// The block stands upright on the start tile, the bridges are as drawn
+pub fn start_state(level: &Level) -> BlockState {
+    BlockState {
+        block: Block {
+            x: level.start.0 as isize,
+            y: level.start.1 as isize,
+            orientation: Orientation::Standing,
+        },
+        switched: false,
+    }
+}
+
+/// The level is won once the block stands upright on the hole
+pub fn is_won(level: &Level, state: &BlockState) -> bool {
+    let block = state.block;
+    block.orientation == Orientation::Standing
+        && (block.x, block.y) == (level.hole.0 as isize, level.hole.1 as isize)
+}
+
+/// Block after rolling it over one of its edges
+pub fn roll_block(block: &Block, direction: char) -> Option<Block> {
+    let Block { x, y, orientation } = *block;
+    let (x, y, orientation) = match (orientation, direction) {
+        (Orientation::Standing, '^') => (x, y - 2, Orientation::LyingY),
+        (Orientation::Standing, 'v') => (x, y + 1, Orientation::LyingY),
+        (Orientation::Standing, '<') => (x - 2, y, Orientation::LyingX),
+        (Orientation::Standing, '>') => (x + 1, y, Orientation::LyingX),
+        (Orientation::LyingX, '^') => (x, y - 1, Orientation::LyingX),
+        (Orientation::LyingX, 'v') => (x, y + 1, Orientation::LyingX),
+        (Orientation::LyingX, '<') => (x - 1, y, Orientation::Standing),
+        (Orientation::LyingX, '>') => (x + 2, y, Orientation::Standing),
+        (Orientation::LyingY, '^') => (x, y - 1, Orientation::Standing),
+        (Orientation::LyingY, 'v') => (x, y + 2, Orientation::Standing),
+        (Orientation::LyingY, '<') => (x - 1, y, Orientation::LyingY),
+        (Orientation::LyingY, '>') => (x + 1, y, Orientation::LyingY),
+        _ => return None,
+    };
+    Some(Block { x, y, orientation })
+}
+
+/// State after rolling the block, `None` when the block falls off the level or breaks a fragile
+/// tile. Every switch under the block flips the bridges once it landed.
+pub fn roll(level: &Level, state: &BlockState, direction: char) -> Option<BlockState> {
+    let block = roll_block(&state.block, direction)?;
+    let mut switched = state.switched;
+    for (x, y) in block.cells() {
+        match level.tile(x, y) {
+            Tile::Void => return None,
+            Tile::Bridge { open } if open == state.switched => return None,
+            Tile::Fragile if block.orientation == Orientation::Standing => return None,
+            Tile::Switch => switched = !switched,
+            _ => {}
+        }
+    }
+    Some(BlockState { block, switched })
+}
+
+/// Breadth-first search over the block states, returns the shortest path to the hole
+pub fn find_shortest_path(level: &Level) -> Option<String> {
+    let start = start_state(level);
+    if is_won(level, &start) {
+        return Some(String::new());
+    }
+    let mut previous: HashMap<BlockState, (BlockState, char)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(state) = queue.pop_front() {
+        for direction in MOVES {
+            let next = match roll(level, &state, direction) {
+                Some(next) => next,
+                None => continue,
+            };
+            if next == start || previous.contains_key(&next) {
+                continue;
+            }
+            previous.insert(next, (state, direction));
+            if is_won(level, &next) {
+                let mut path = vec![];
+                let mut current = next;
+                while let Some((before, direction)) = previous.get(&current) {
+                    path.push(*direction);
+                    current = *before;
+                }
+                return Some(path.into_iter().rev().collect());
+            }
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge_generator::validate_bloxorz_challenge;
+
+    fn input(level: &str) -> BloxorzInput {
+        BloxorzInput {
+            level: level.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_level() {
+        let level = parse_level("oS\n  ~@=+X").unwrap();
+        assert_eq!((level.width, level.height), (7, 2));
+        assert_eq!(level.start, (1, 0));
+        assert_eq!(level.hole, (6, 1));
+        assert_eq!(level.tile(0, 0), Tile::Floor);
+        assert_eq!(level.tile(6, 0), Tile::Void);
+        assert_eq!(level.tile(2, 1), Tile::Fragile);
+        assert_eq!(level.tile(3, 1), Tile::Switch);
+        assert_eq!(level.tile(4, 1), Tile::Bridge { open: false });
+        assert_eq!(level.tile(5, 1), Tile::Bridge { open: true });
+        assert_eq!(level.tile(-1, 0), Tile::Void);
+    }
+
+    #[test]
+    fn test_parse_level_errors() {
+        assert_eq!(parse_level("ooX"), Err(BloxorzError::MissingStart));
+        assert_eq!(parse_level("Soo"), Err(BloxorzError::MissingHole));
+        assert_eq!(parse_level("S#X"), Err(BloxorzError::UnknownTile('#')));
+    }
+
+    #[test]
+    fn test_roll_block_and_back() {
+        let standing = Block {
+            x: 3,
+            y: 3,
+            orientation: Orientation::Standing,
+        };
+        for (direction, back) in [('^', 'v'), ('v', '^'), ('<', '>'), ('>', '<')] {
+            let rolled = roll_block(&standing, direction).unwrap();
+            assert_ne!(rolled.orientation, Orientation::Standing);
+            assert_eq!(roll_block(&rolled, back), Some(standing));
+        }
+        assert_eq!(roll_block(&standing, 'x'), None);
+    }
+
+    #[test]
+    fn test_solve_straight_line() {
+        let level = input("SoooooX");
+        let output = solve_bloxorz(&level).unwrap();
+        assert_eq!(output.path, ">>>>");
+        assert!(validate_bloxorz_challenge(&level, &output));
+    }
+
+    #[test]
+    fn test_solve_with_switch() {
+        let level = input("ooo    \nS@o==oX\nooo    ");
+        let output = solve_bloxorz(&level).unwrap();
+        assert!(validate_bloxorz_challenge(&level, &output));
+        // Without the switch the bridge stays closed
+        assert_eq!(
+            solve_bloxorz(&input("ooo    \nSoo==oX\nooo    ")).unwrap_err(),
+            BloxorzError::NoPath
+        );
+    }
+
+    #[test]
+    fn test_fragile_tile_breaks_under_a_standing_block() {
+        assert_eq!(
+            solve_bloxorz(&input("Soo~ooX")).unwrap_err(),
+            BloxorzError::NoPath
+        );
+        let level = input("So~o~oX");
+        let output = solve_bloxorz(&level).unwrap();
+        assert_eq!(output.path, ">>>>");
+        assert!(validate_bloxorz_challenge(&level, &output));
+    }
+
+    #[test]
+    fn test_solve_without_path() {
+        assert_eq!(
+            solve_bloxorz(&input("So X")).unwrap_err(),
+            BloxorzError::NoPath
+        );
+    }
+}
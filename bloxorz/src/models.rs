@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BloxorzInput {
+    /// Level drawn line by line, see `bloxorz.md` for the tiles
+    pub level: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BloxorzOutput {
+    /// Rolls of the block, `^`, `v`, `<` or `>`
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bloxorz(pub BloxorzInput);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Tile {
+    Void,
+    Floor,
+    /// Breaks when the block stands upright on it
+    Fragile,
+    /// Opens the closed bridges and closes the open ones when the block touches it
+    Switch,
+    /// Floor only while open
+    Bridge {
+        open: bool,
+    },
+    Hole,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Orientation {
+    Standing,
+    /// Lying on `(x, y)` and `(x + 1, y)`
+    LyingX,
+    /// Lying on `(x, y)` and `(x, y + 1)`
+    LyingY,
+}
+
+/// Block of 1x1x2, `x` and `y` are its cell nearest to the top left corner
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Block {
+    pub x: isize,
+    pub y: isize,
+    pub orientation: Orientation,
+}
+
+impl Block {
+    /// Cells the block rests on
+    pub fn cells(&self) -> Vec<(isize, isize)> {
+        match self.orientation {
+            Orientation::Standing => vec![(self.x, self.y)],
+            Orientation::LyingX => vec![(self.x, self.y), (self.x + 1, self.y)],
+            Orientation::LyingY => vec![(self.x, self.y), (self.x, self.y + 1)],
+        }
+    }
+}
+
+/// Position of the block and whether the bridges were switched an odd number of times
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct BlockState {
+    pub block: Block,
+    pub switched: bool,
+}
+
+/// Level stored row by row in a single vector, `tiles[y * width + x]`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Level {
+    pub tiles: Vec<Tile>,
+    pub width: usize,
+    pub height: usize,
+    pub start: (usize, usize),
+    pub hole: (usize, usize),
+}
+
+impl Level {
+    /// Tiles outside of the level are void
+    pub fn tile(&self, x: isize, y: isize) -> Tile {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return Tile::Void;
+        }
+        self.tiles[y as usize * self.width + x as usize]
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BloxorzError {
+    /// The level has no `S` start
+    MissingStart,
+    /// The level has no `X` hole
+    MissingHole,
+    UnknownTile(char),
+    /// The block cannot reach the hole standing upright
+    NoPath,
+}
+
+impl std::fmt::Display for BloxorzError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BloxorzError::MissingStart => write!(f, "the level has no start"),
+            BloxorzError::MissingHole => write!(f, "the level has no hole"),
+            BloxorzError::UnknownTile(tile) => write!(f, "unknown tile {:?}", tile),
+            BloxorzError::NoPath => write!(f, "the block cannot reach the hole"),
+        }
+    }
+}
+
+impl std::error::Error for BloxorzError {}
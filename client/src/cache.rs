@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fs;
+
+use log::{debug, warn};
+
+use shared::challenge::{ChallengeAnswer, ChallengeType};
+use shared::registry::registry;
+
+/// Answers of the challenges already solved, kept in a JSON file between two games and keyed
+/// by [`ChallengeType::canonical_hash`]
+pub struct SolutionCache {
+    path: String,
+    answers: HashMap<String, ChallengeAnswer>,
+    /// Answers were added since the file was read or written
+    is_dirty: bool,
+}
+
+impl SolutionCache {
+    /// Reads the cache file, a missing or unreadable file gives an empty cache
+    pub fn load(path: &str) -> SolutionCache {
+        let answers = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                warn!("Ignoring invalid cache file {}: {}", path, err);
+                HashMap::new()
+            }),
+            Err(err) => {
+                debug!("No cache file {}: {}", path, err);
+                HashMap::new()
+            }
+        };
+        SolutionCache {
+            path: path.to_string(),
+            answers,
+            is_dirty: false,
+        }
+    }
+
+    /// Cached answer of the challenge, only when it still solves it
+    pub fn get(&mut self, challenge: &ChallengeType) -> Option<ChallengeAnswer> {
+        let hash = challenge.canonical_hash();
+        let answer = self.answers.get(&hash)?.clone();
+        if challenge.verify(answer.clone()) {
+            return Some(answer);
+        }
+        warn!("Dropping cached answer {:?} of {:?}", answer, challenge);
+        self.answers.remove(&hash);
+        None
+    }
+
+    /// Keeps the answer of a challenge registered as recurring when it solves it, the file is
+    /// written by [`SolutionCache::save`]
+    pub fn insert(&mut self, challenge: &ChallengeType, answer: &ChallengeAnswer) {
+        if !registry().is_recurring(&challenge.name) || !challenge.verify(answer.clone()) {
+            return;
+        }
+        self.answers
+            .insert(challenge.canonical_hash(), answer.clone());
+        self.is_dirty = true;
+    }
+
+    /// Writes the cache file when answers were added, called once the game is over
+    pub fn save(&mut self) {
+        if !self.is_dirty {
+            return;
+        }
+        let result = serde_json::to_string(&self.answers)
+            .map_err(|err| err.to_string())
+            .and_then(|json| fs::write(&self.path, json).map_err(|err| err.to_string()));
+        match result {
+            Ok(()) => self.is_dirty = false,
+            Err(err) => warn!("Could not write cache file {}: {}", self.path, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use hashcash::algorithm::HashAlgorithm;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+    use shared::registry::{GeneratorContext, SolverContext};
+
+    use super::*;
+
+    /// Path of a cache file of the test, removed beforehand
+    fn cache_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "solution-cache-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Level of the bloxorz pack, the only recurring builtin challenge
+    fn bloxorz() -> ChallengeType {
+        registry()
+            .generate(
+                "bloxorz",
+                &GeneratorContext::new(0),
+                &mut StdRng::seed_from_u64(0),
+            )
+            .unwrap()
+    }
+
+    fn bloxorz_answer(path: &str) -> ChallengeAnswer {
+        ChallengeAnswer {
+            name: "Bloxorz".to_string(),
+            output: json!({ "path": path }),
+        }
+    }
+
+    fn solved_bloxorz_answer() -> ChallengeAnswer {
+        registry()
+            .solve(&bloxorz(), &SolverContext::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let path = cache_path("missing");
+        let mut cache = SolutionCache::load(path.to_str().unwrap());
+        assert!(cache.answers.is_empty());
+        assert_eq!(cache.get(&bloxorz()), None);
+    }
+
+    #[test]
+    fn test_load_invalid_file() {
+        let path = cache_path("invalid");
+        fs::write(&path, "not a cache").unwrap();
+        let cache = SolutionCache::load(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(cache.answers.is_empty());
+    }
+
+    #[test]
+    fn test_insert_only_verified_recurring_answers() {
+        let path = cache_path("insert");
+        let mut cache = SolutionCache::load(path.to_str().unwrap());
+        cache.insert(&bloxorz(), &bloxorz_answer(""));
+        assert_eq!(cache.get(&bloxorz()), None);
+        let maze = ChallengeType {
+            name: "MonstrousMaze".to_string(),
+            input: json!({"grid": "I X", "endurance": 2}),
+        };
+        let maze_answer = ChallengeAnswer {
+            name: "MonstrousMaze".to_string(),
+            output: json!({"path": ">>"}),
+        };
+        assert!(maze.verify(maze_answer.clone()));
+        cache.insert(&maze, &maze_answer);
+        let hash_cash = ChallengeType {
+            name: "MD5HashCash".to_string(),
+            input: json!({"complexity": 0, "message": "hello"}),
+        };
+        let hash_cash_answer = ChallengeAnswer {
+            name: "MD5HashCash".to_string(),
+            output: json!({"seed": 0, "hashcode": HashAlgorithm::MD5.hashcode(0, "hello")}),
+        };
+        assert!(hash_cash.verify(hash_cash_answer.clone()));
+        cache.insert(&hash_cash, &hash_cash_answer);
+        assert!(cache.answers.is_empty());
+
+        cache.insert(&bloxorz(), &solved_bloxorz_answer());
+        cache.save();
+        let mut reloaded = SolutionCache::load(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.get(&bloxorz()), Some(solved_bloxorz_answer()));
+    }
+
+    #[test]
+    fn test_get_drops_answers_that_no_longer_verify() {
+        let path = cache_path("stale");
+        let mut cache = SolutionCache::load(path.to_str().unwrap());
+        cache
+            .answers
+            .insert(bloxorz().canonical_hash(), bloxorz_answer(""));
+        assert_eq!(cache.get(&bloxorz()), None);
+        assert!(cache.answers.is_empty());
+    }
+}
@@ -1,34 +1,36 @@
-use std::collections::HashSet;
 use std::net::Shutdown;
-use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::Sender;
-use std::{
-    io::{Read, Write},
-    net::TcpStream,
-    thread,
-};
+use std::{net::TcpStream, thread};
 
 use clap::Parser;
 use log::{debug, error, trace};
 use rand;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use hashcash::solver::{HashcashSolver, SolverConfig};
+use recover_secret::challenge_generator::dictionary_words;
+use recover_secret::dictionary::Trie;
+use serde_json::Value;
 
-use hashcash::hashcash::{THREAD_COUNT, THREAD_SEED_SLICE};
-use shared::challenge::{Challenge, ChallengeAnswer, ChallengeType, DictionaryChallenge};
+use shared::challenge::{ChallengeAnswer, ChallengeType};
+use shared::codec::{FrameError, FrameReader, FrameWriter};
 use shared::message::Message::ChallengeResult;
 use shared::message::{Message, PublicLeaderBoard};
+use shared::registry::{registry, SolverContext};
 use shared::subscribe::SubscribeResult;
 use utils::file_utils::read_dic_file_macro;
-use utils::string_utils::generate_dictionary_hashmap;
 
+use crate::cache::SolutionCache;
 use crate::strategies::{
     BottomTargetStrategy, RandomTargetStrategy, TargetStrategy, TargetStrategyType,
     TopTargetStrategy,
 };
 use crate::ui::{start_ui_display, ClientData};
 
+mod cache;
 mod strategies;
 mod ui;
 
@@ -36,9 +38,9 @@ mod ui;
 #[derive(Parser, Default, Debug)]
 #[clap(author, version, about, long_about = None)]
 pub struct ClientArgs {
-    /// Name of the player must be unique
-    #[clap(short, long, value_parser, default_value = generate_random_username())]
-    username: String,
+    /// Name of the player must be unique, a random one is drawn when it is missing
+    #[clap(short, long, value_parser)]
+    username: Option<String>,
 
     /// Server IP
     #[clap(short, value_parser, default_value = "127.0.0.1")]
@@ -60,9 +62,13 @@ pub struct ClientArgs {
     #[clap(long, value_parser, default_value_t = false)]
     pub load_dictionary: bool,
 
-    /// Enable cheat mode for recover secret challenge
+    /// File keeping the answers of the solved challenges between two games
+    #[clap(long, value_parser, default_value = "challenge-cache.json")]
+    pub cache_file: String,
+
+    /// Solve every challenge without reading nor writing the cache file
     #[clap(long, value_parser, default_value_t = false)]
-    pub cheat: bool,
+    pub no_cache: bool,
 
     /// Log level
     #[clap(short, long, value_parser, default_value = "info")]
@@ -71,12 +77,14 @@ pub struct ClientArgs {
     /// Enable client ui display
     #[clap(long, value_parser, default_value_t = false)]
     pub display_gui: bool,
+
+    /// Seed of the strategy and target choices, random by default
+    #[clap(long, value_parser)]
+    pub seed: Option<u64>,
 }
 
 fn main() {
     let args = ClientArgs::parse();
-    THREAD_COUNT.store(args.thread_count, Ordering::Relaxed);
-    THREAD_SEED_SLICE.store(args.thread_seed_slice, Ordering::Relaxed);
     std::env::set_var("RUST_LOG", &args.log_level);
     pretty_env_logger::init();
     match TcpStream::connect(format!("{}:{}", args.ip, args.port).as_str()) {
@@ -96,58 +104,52 @@ fn main() {
     }
 }
 
-fn solve_challenge(
-    challenge: ChallengeType,
-    dictionary_hashmap: &Option<HashSet<String>>,
-    cheat: &bool,
-) -> ChallengeAnswer {
-    match challenge {
-        ChallengeType::MD5HashCash(challenge) => ChallengeAnswer::MD5HashCash(challenge.solve()),
-        ChallengeType::RecoverSecret(challenge) => {
-            return if let Some(dictionary_hashmap) = dictionary_hashmap {
-                if *cheat {
-                    ChallengeAnswer::RecoverSecret(challenge.solve_secret_cheat())
-                } else {
-                    ChallengeAnswer::RecoverSecret(challenge.solve_secret(dictionary_hashmap))
-                }
-            } else {
-                if *cheat {
-                    ChallengeAnswer::RecoverSecret(challenge.solve_cheat())
-                } else {
-                    ChallengeAnswer::RecoverSecret(challenge.solve())
-                }
-            };
+/// Solves the challenge with the registered solver, an unsolvable challenge is answered with a
+/// null output the server will reject
+fn solve_challenge(challenge: &ChallengeType, context: &SolverContext) -> ChallengeAnswer {
+    registry().solve(challenge, context).unwrap_or_else(|err| {
+        error!("Cannot solve challenge: {}", err);
+        ChallengeAnswer {
+            name: challenge.name.clone(),
+            output: Value::Null,
         }
-        ChallengeType::MonstrousMaze(challenge) => {
-            ChallengeAnswer::MonstrousMaze(challenge.solve())
-        }
-    }
+    })
 }
 
 pub struct Client {
     public_leader_board: PublicLeaderBoard,
     username: String,
     next_target_strategy: TargetStrategyType,
-    dictionary_hashmap: Option<HashSet<String>>,
-    cheat: bool,
+    hashcash_solver: HashcashSolver,
+    dictionary: Option<Trie>,
+    cache: Option<SolutionCache>,
     ui_enabled: bool,
     ui_writer: Sender<ClientData>,
+    rng: StdRng,
 }
 
 impl Client {
     pub fn new(args: &ClientArgs, ui_writer: Sender<ClientData>) -> Client {
-        let mut rng = rand::thread_rng();
+        let mut rng = match args.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
         // Load dictionary file
-        let dictionary_hashmap;
-        let username = args.username.clone();
+        let dictionary;
+        let username = args
+            .username
+            .clone()
+            .unwrap_or_else(|| generate_random_username(&mut rng));
         if args.load_dictionary {
             debug!("Reading dictionary file...");
-            let dictionary = read_dic_file_macro();
-            debug!("Generating hashmap...");
-            dictionary_hashmap = Some(generate_dictionary_hashmap(&dictionary));
+            let words = read_dic_file_macro();
+            debug!("Generating trie...");
+            let mut trie = Trie::from_words(dictionary_words());
+            words.split_whitespace().for_each(|word| trie.insert(word));
+            dictionary = Some(trie);
             debug!("Done !");
         } else {
-            dictionary_hashmap = None;
+            dictionary = None;
         }
 
         let next_target_strategy = match rng.gen_range(0..=2) {
@@ -175,10 +177,15 @@ impl Client {
             public_leader_board: vec![],
             username,
             next_target_strategy,
-            dictionary_hashmap,
-            cheat: args.cheat,
+            hashcash_solver: HashcashSolver::new(SolverConfig {
+                thread_count: args.thread_count,
+                seed_slice: args.thread_seed_slice,
+            }),
+            dictionary,
+            cache: (!args.no_cache).then(|| SolutionCache::load(&args.cache_file)),
             ui_enabled: args.display_gui,
             ui_writer,
+            rng,
         }
     }
 
@@ -194,45 +201,37 @@ impl Client {
         self.start_message_listener(stream_cpy, thread_writer);
     }
 
-    fn start_message_listener(mut self, mut stream: TcpStream, thread_writer: Sender<Message>) {
-        let mut buf_size = [0; 4];
+    fn start_message_listener(mut self, stream: TcpStream, thread_writer: Sender<Message>) {
+        let mut reader = FrameReader::new(&stream);
 
         loop {
-            stream
-                .read(&mut buf_size)
-                .expect("Could not read stream message size");
-
-            let res_size = u32::from_be_bytes(buf_size);
-            if res_size == 0 {
-                debug!("Received 0 size message");
-                continue;
-            }
-
-            let mut buf = vec![0; res_size as usize];
-
-            stream
-                .read(&mut buf)
-                .expect("Could not read stream message");
-
-            let string_receive = String::from_utf8_lossy(&buf);
-
-            match serde_json::from_str(&string_receive) {
+            match reader.read_message() {
                 Ok(message) => {
                     let message = self.dispatch_messages(message, &thread_writer);
-                    match message {
-                        Message::EndOfGame { .. } => {
-                            debug!("Shutting down reader stream");
-                            stream
-                                .shutdown(Shutdown::Both)
-                                .expect("shutdown call failed");
-                            break;
-                        }
-                        _ => {}
+                    if let Message::EndOfGame { .. } = message {
+                        debug!("Shutting down reader stream");
+                        stream
+                            .shutdown(Shutdown::Both)
+                            .expect("shutdown call failed");
+                        break;
                     }
                 }
-                Err(err) => error!("Error while parsing message {:?}", err),
+                Err(err @ (FrameError::InvalidUtf8(_) | FrameError::InvalidJson(_))) => {
+                    error!("Error while parsing message {}", err)
+                }
+                Err(FrameError::EndOfStream) => {
+                    debug!("Server closed the connection");
+                    break;
+                }
+                Err(err) => {
+                    error!("Error while reading message {}", err);
+                    break;
+                }
             }
         }
+        if let Some(cache) = &mut self.cache {
+            cache.save();
+        }
     }
 
     fn dispatch_messages(&mut self, message: Message, thread_writer: &Sender<Message>) -> Message {
@@ -247,18 +246,17 @@ impl Client {
                     .expect("Could not send subscribe message");
             }
             Message::Challenge(challenge) => {
-                let challenge_answer =
-                    solve_challenge(challenge, &self.dictionary_hashmap, &self.cheat);
+                let challenge_answer = self.answer_challenge(challenge);
 
                 let next_target = match self.next_target_strategy.clone() {
                     TargetStrategyType::RandomTargetStrategy(strategy) => {
-                        strategy.next_target(&self.public_leader_board)
+                        strategy.next_target(&self.public_leader_board, &mut self.rng)
                     }
                     TargetStrategyType::TopTargetStrategy(strategy) => {
-                        strategy.next_target(&self.public_leader_board)
+                        strategy.next_target(&self.public_leader_board, &mut self.rng)
                     }
                     TargetStrategyType::BottomTargetStrategy(strategy) => {
-                        strategy.next_target(&self.public_leader_board)
+                        strategy.next_target(&self.public_leader_board, &mut self.rng)
                     }
                 };
                 debug!("Selected next target: {:?}", next_target);
@@ -301,8 +299,26 @@ impl Client {
         message
     }
 
-    fn start_message_sender(&self, mut stream: TcpStream, thread_reader: Receiver<Message>) {
+    /// Answers from the cache when it knows the challenge, else solves it and caches the answer
+    fn answer_challenge(&mut self, challenge: ChallengeType) -> ChallengeAnswer {
+        if let Some(answer) = self.cache.as_mut().and_then(|cache| cache.get(&challenge)) {
+            debug!("Answer found in cache: {:?}", answer);
+            return answer;
+        }
+        let context = SolverContext {
+            hashcash_solver: Some(&self.hashcash_solver),
+            dictionary: self.dictionary.as_ref(),
+        };
+        let answer = solve_challenge(&challenge, &context);
+        if let Some(cache) = &mut self.cache {
+            cache.insert(&challenge, &answer);
+        }
+        answer
+    }
+
+    fn start_message_sender(&self, stream: TcpStream, thread_reader: Receiver<Message>) {
         thread::spawn(move || {
+            let mut writer = FrameWriter::new(&stream);
             for message in thread_reader {
                 match message {
                     Message::EndOfGame { .. } => {
@@ -310,16 +326,8 @@ impl Client {
                         break;
                     }
                     _ => {
-                        if let Ok(message) = serde_json::to_string(&message) {
-                            let bytes_message = message.as_bytes();
-                            let message_size = bytes_message.len() as u32;
-                            let message_length_as_bytes = message_size.to_be_bytes();
-                            let result =
-                                stream.write(&[&message_length_as_bytes, bytes_message].concat());
-                            debug!("Write result : {:?}, message: {}", result, message);
-                        } else {
-                            error!("Could not serialize message");
-                        }
+                        let result = writer.write_message(&message);
+                        debug!("Write result : {:?}, message: {:?}", result, message);
                     }
                 }
             }
@@ -327,9 +335,7 @@ impl Client {
     }
 }
 
-fn generate_random_username() -> &'static str {
-    let mut rng = rand::thread_rng();
+fn generate_random_username<R: Rng>(rng: &mut R) -> String {
     let n1: u8 = rng.gen();
-    let username = "user".to_string() + &*n1.to_string();
-    Box::leak(username.into_boxed_str())
+    "user".to_string() + &*n1.to_string()
 }
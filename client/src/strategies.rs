@@ -1,4 +1,5 @@
 use rand::seq::IteratorRandom;
+use rand::Rng;
 
 use shared::message::PublicLeaderBoard;
 
@@ -26,7 +27,7 @@ pub enum TargetStrategyType {
 
 pub trait TargetStrategy {
     fn new(current_name: String) -> Self;
-    fn next_target(self, public_leader_board: &PublicLeaderBoard) -> String;
+    fn next_target<R: Rng>(self, public_leader_board: &PublicLeaderBoard, rng: &mut R) -> String;
 }
 
 impl TargetStrategy for TopTargetStrategy {
@@ -34,7 +35,7 @@ impl TargetStrategy for TopTargetStrategy {
         TopTargetStrategy { current_name }
     }
 
-    fn next_target(self, public_leader_board: &PublicLeaderBoard) -> String {
+    fn next_target<R: Rng>(self, public_leader_board: &PublicLeaderBoard, _rng: &mut R) -> String {
         return public_leader_board
             .iter()
             .filter(|player| player.name != self.current_name && player.is_active)
@@ -50,7 +51,7 @@ impl TargetStrategy for BottomTargetStrategy {
         BottomTargetStrategy { current_name }
     }
 
-    fn next_target(self, public_leader_board: &PublicLeaderBoard) -> String {
+    fn next_target<R: Rng>(self, public_leader_board: &PublicLeaderBoard, _rng: &mut R) -> String {
         return public_leader_board
             .iter()
             .filter(|player| player.name != self.current_name && player.is_active)
@@ -66,12 +67,11 @@ impl TargetStrategy for RandomTargetStrategy {
         RandomTargetStrategy { current_name }
     }
 
-    fn next_target(self, public_leader_board: &PublicLeaderBoard) -> String {
-        let mut rng = rand::thread_rng();
+    fn next_target<R: Rng>(self, public_leader_board: &PublicLeaderBoard, rng: &mut R) -> String {
         return public_leader_board
             .iter()
             .filter(|player| player.name != self.current_name && player.is_active)
-            .choose(&mut rng)
+            .choose(rng)
             .expect("No more players in the game")
             .name
             .clone();
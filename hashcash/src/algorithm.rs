@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::utils::{format_hashcode, seed_to_hex};
+
+/// Hash function of a proof of work, the seed and message rules are the same for all of them
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    MD5,
+    SHA1,
+    SHA256,
+}
+
+impl HashAlgorithm {
+    /// Size of the digest in bytes
+    pub fn digest_length(self) -> usize {
+        match self {
+            HashAlgorithm::MD5 => 16,
+            HashAlgorithm::SHA1 => 20,
+            HashAlgorithm::SHA256 => 32,
+        }
+    }
+
+    /// Size of the hashcode, the digest written in hexadecimal
+    pub fn hashcode_length(self) -> usize {
+        2 * self.digest_length()
+    }
+
+    /// Hashcode of a seed for a message: the digest of `format!("{:016X}", seed) + message`
+    /// in upper case hexadecimal
+    pub fn hashcode(self, seed: u64, message: &str) -> String {
+        let message = message.as_bytes();
+        match self {
+            HashAlgorithm::MD5 => format_hashcode(&Md5Hasher::hash_seed(seed, message)),
+            HashAlgorithm::SHA1 => format_hashcode(&Sha1Hasher::hash_seed(seed, message)),
+            HashAlgorithm::SHA256 => format_hashcode(&Sha256Hasher::hash_seed(seed, message)),
+        }
+    }
+}
+
+/// Digest of the hexadecimal seed followed by the message, computed without allocation so the
+/// search loop can be specialized for each algorithm
+pub(crate) trait SeedHasher {
+    type Digest: AsRef<[u8]>;
+
+    fn hash_seed(seed: u64, message: &[u8]) -> Self::Digest;
+}
+
+pub(crate) struct Md5Hasher;
+
+pub(crate) struct Sha1Hasher;
+
+pub(crate) struct Sha256Hasher;
+
+impl SeedHasher for Md5Hasher {
+    type Digest = [u8; 16];
+
+    /// The seed comes first so the MD5 state cannot be shared between seeds, only the message
+    /// bytes are.
+    fn hash_seed(seed: u64, message: &[u8]) -> [u8; 16] {
+        let mut context = md5::Context::new();
+        context.consume(seed_to_hex(seed));
+        context.consume(message);
+        context.compute().0
+    }
+}
+
+impl SeedHasher for Sha1Hasher {
+    type Digest = [u8; 20];
+
+    fn hash_seed(seed: u64, message: &[u8]) -> [u8; 20] {
+        let mut hasher = Sha1::new();
+        hasher.update(seed_to_hex(seed));
+        hasher.update(message);
+        hasher.finalize().into()
+    }
+}
+
+impl SeedHasher for Sha256Hasher {
+    type Digest = [u8; 32];
+
+    fn hash_seed(seed: u64, message: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(seed_to_hex(seed));
+        hasher.update(message);
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashcode() {
+        assert_eq!(
+            HashAlgorithm::MD5.hashcode(0x2a, "hello"),
+            format!("{:032X}", md5::compute("000000000000002Ahello"))
+        );
+        let expected: String = Sha1::digest("000000000000002Ahello")
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        assert_eq!(HashAlgorithm::SHA1.hashcode(0x2a, "hello"), expected);
+        let expected: String = Sha256::digest("000000000000002Ahello")
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        assert_eq!(HashAlgorithm::SHA256.hashcode(0x2a, "hello"), expected);
+    }
+
+    #[test]
+    fn test_hashcode_length() {
+        for algorithm in [
+            HashAlgorithm::MD5,
+            HashAlgorithm::SHA1,
+            HashAlgorithm::SHA256,
+        ] {
+            assert_eq!(
+                algorithm.hashcode(0, "hello").len(),
+                algorithm.hashcode_length()
+            );
+        }
+    }
+}
@@ -1 +1,6 @@
 pub const HASHCASH_MESSAGE_LENGTH: usize = 40;
+/// Bounds of the complexities of the generated challenges
+pub const MIN_COMPLEXITY: u32 = 5;
+pub const MAX_COMPLEXITY: u32 = 23;
+/// Seeds reserved at once by a worker before checking the shared counter again
+pub const DEFAULT_SEED_SLICE: u64 = 1000;
@@ -1,33 +1,69 @@
 extern crate rand;
 
+use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
 
 use crate::config;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MD5HashCashOutput {
+pub struct HashCashOutput {
     pub seed: u64,
     pub hashcode: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MD5HashCashInput {
+pub struct HashCashInput {
     pub complexity: u32,
     pub message: String,
 }
 
+pub type MD5HashCashOutput = HashCashOutput;
+
+pub type MD5HashCashInput = HashCashInput;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MD5HashCash(pub HashCashInput);
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MD5HashCash(pub MD5HashCashInput);
-
-impl MD5HashCashInput {
-    pub fn new() -> MD5HashCashInput {
-        let charset = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
-        let mut rng = thread_rng();
-        let complexity: u32 = rng.gen_range(5..24);
-        MD5HashCashInput {
+pub struct SHA1HashCash(pub HashCashInput);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SHA256HashCash(pub HashCashInput);
+
+const MESSAGE_CHARSET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+impl HashCashInput {
+    pub fn new() -> HashCashInput {
+        HashCashInput::generate(&mut thread_rng())
+    }
+
+    /// Random message to hash with a random complexity
+    pub fn generate<R: Rng>(rng: &mut R) -> HashCashInput {
+        let complexity = rng.gen_range(config::MIN_COMPLEXITY..=config::MAX_COMPLEXITY);
+        HashCashInput::with_complexity(complexity, rng)
+    }
+
+    /// Random message to hash with a complexity growing with a difficulty between 0 and 1
+    pub fn with_difficulty<R: Rng>(difficulty: f64, rng: &mut R) -> HashCashInput {
+        let range = (config::MAX_COMPLEXITY - config::MIN_COMPLEXITY) as f64;
+        let complexity =
+            config::MIN_COMPLEXITY + (difficulty.clamp(0.0, 1.0) * range).round() as u32;
+        HashCashInput::with_complexity(complexity, rng)
+    }
+
+    /// Random message to hash with the given complexity
+    pub fn with_complexity<R: Rng>(complexity: u32, rng: &mut R) -> HashCashInput {
+        let message = (0..config::HASHCASH_MESSAGE_LENGTH)
+            .map(|_| {
+                *MESSAGE_CHARSET
+                    .choose(rng)
+                    .expect("The charset is not empty") as char
+            })
+            .collect();
+        HashCashInput {
             complexity,
-            message: random_string::generate(config::HASHCASH_MESSAGE_LENGTH, charset),
+            message,
         }
     }
 }
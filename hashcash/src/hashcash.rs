@@ -1,68 +1,132 @@
-use std::sync::atomic::AtomicU64;
-use std::sync::atomic::Ordering;
-use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::thread;
 
-use crate::dto::MD5HashCashOutput;
-use crate::utils::check_hash;
+use crate::algorithm::{HashAlgorithm, Md5Hasher, SeedHasher, Sha1Hasher, Sha256Hasher};
+use crate::dto::{HashCashOutput, MD5HashCashOutput};
+use crate::solver::SolverConfig;
+use crate::utils::{check_hash, format_hashcode, leading_zero_bits};
 
 pub struct Hashcash;
 
-pub static THREAD_COUNT: AtomicUsize = AtomicUsize::new(1);
-pub static THREAD_SEED_SLICE: AtomicU64 = AtomicU64::new(1000);
-
 impl Hashcash {
+    /// Solves with fresh threads on every call, a [`crate::solver::HashcashSolver`] keeps them alive
     pub fn solve(message: String, complexity: u32) -> MD5HashCashOutput {
-        let thread_count = THREAD_COUNT.load(Ordering::Relaxed);
-        let thread_seed_slice = THREAD_SEED_SLICE.load(Ordering::Relaxed);
+        Hashcash::solve_with_config(message, complexity, &SolverConfig::default())
+    }
+
+    pub fn solve_with_config(
+        message: String,
+        complexity: u32,
+        config: &SolverConfig,
+    ) -> MD5HashCashOutput {
+        Hashcash::solve_hash(HashAlgorithm::MD5, message, complexity, config)
+    }
+
+    pub fn solve_hash(
+        algorithm: HashAlgorithm,
+        message: String,
+        complexity: u32,
+        config: &SolverConfig,
+    ) -> HashCashOutput {
         let seed_counter = Arc::new(AtomicU64::new(0));
         let is_solved = Arc::new(AtomicBool::new(false));
         let (worker_tx, worker_rx) = mpsc::channel();
-        for _ in 0..thread_count {
-            let thread_seed_slice = thread_seed_slice.clone();
+        for _ in 0..config.thread_count.max(1) {
+            let seed_slice = config.seed_slice;
             let worker_tx = worker_tx.clone();
             let seed_counter = seed_counter.clone();
             let is_solved = is_solved.clone();
             let message = message.clone();
-            thread::spawn(move || 'outer: loop {
-                let seed = seed_counter.fetch_add(thread_seed_slice, Ordering::Relaxed);
-                for seed in seed..seed + thread_seed_slice {
-                    if is_solved.load(Ordering::Relaxed) {
-                        break 'outer;
-                    }
-                    let hash = md5::compute(format!("{:016X}", seed) + &message);
-                    let md5 = format!("{:032X}", hash);
-                    if !check_hash(complexity, md5.clone()) {
-                        continue;
-                    }
-                    worker_tx
-                        .send(MD5HashCashOutput {
-                            seed,
-                            hashcode: md5.to_string(),
-                        })
-                        .expect("Error while sending answer to main thread");
+            thread::spawn(move || {
+                let output = search(
+                    algorithm,
+                    &message,
+                    complexity,
+                    &seed_counter,
+                    seed_slice,
+                    &is_solved,
+                );
+                if let Some(output) = output {
                     is_solved.store(true, Ordering::Relaxed);
+                    let _ = worker_tx.send(output);
                 }
             });
         }
-        let workers_result = worker_rx.recv();
-        if workers_result.is_err() {
-            panic!("error");
+        worker_rx
+            .recv()
+            .expect("Every hashcash worker stopped without answer")
+    }
+
+    pub fn verify(message: &str, complexity: u32, answer: &MD5HashCashOutput) -> bool {
+        Hashcash::verify_hash(HashAlgorithm::MD5, message, complexity, answer)
+    }
+
+    /// Recomputes the hashcode of the answer seed, a forged or malformed hashcode is rejected
+    pub fn verify_hash(
+        algorithm: HashAlgorithm,
+        message: &str,
+        complexity: u32,
+        answer: &HashCashOutput,
+    ) -> bool {
+        check_hash(algorithm, complexity, &answer.hashcode)
+            && algorithm
+                .hashcode(answer.seed, message)
+                .eq_ignore_ascii_case(&answer.hashcode)
+    }
+}
+
+/// Checks seeds slice by slice until a hashcode reaches the complexity or `stop` is set.
+/// Only the winning digest is formatted, the loop itself does not allocate.
+pub(crate) fn search(
+    algorithm: HashAlgorithm,
+    message: &str,
+    complexity: u32,
+    seed_counter: &AtomicU64,
+    seed_slice: u64,
+    stop: &AtomicBool,
+) -> Option<HashCashOutput> {
+    match algorithm {
+        HashAlgorithm::MD5 => {
+            search_with::<Md5Hasher>(message, complexity, seed_counter, seed_slice, stop)
+        }
+        HashAlgorithm::SHA1 => {
+            search_with::<Sha1Hasher>(message, complexity, seed_counter, seed_slice, stop)
+        }
+        HashAlgorithm::SHA256 => {
+            search_with::<Sha256Hasher>(message, complexity, seed_counter, seed_slice, stop)
         }
-        workers_result.unwrap()
     }
+}
 
-    pub fn verify(hash: String, complexity: u32) -> bool {
-        check_hash(complexity, hash)
+fn search_with<H: SeedHasher>(
+    message: &str,
+    complexity: u32,
+    seed_counter: &AtomicU64,
+    seed_slice: u64,
+    stop: &AtomicBool,
+) -> Option<HashCashOutput> {
+    let message = message.as_bytes();
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        let first_seed = seed_counter.fetch_add(seed_slice, Ordering::Relaxed);
+        for seed in first_seed..first_seed.saturating_add(seed_slice) {
+            let digest = H::hash_seed(seed, message);
+            if leading_zero_bits(digest.as_ref()) >= complexity {
+                return Some(HashCashOutput {
+                    seed,
+                    hashcode: format_hashcode(digest.as_ref()),
+                });
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::check_hash;
-
     use super::*;
 
     #[test]
@@ -70,7 +134,7 @@ mod tests {
         let message = "hello world".to_string();
         let complexity = 5;
         let output = Hashcash::solve(message.clone(), complexity);
-        assert!(check_hash(complexity, output.hashcode));
+        assert!(Hashcash::verify(&message, complexity, &output));
     }
 
     #[test]
@@ -79,7 +143,7 @@ mod tests {
             .to_string();
         let complexity = 5;
         let output = Hashcash::solve(message.clone(), complexity);
-        assert!(check_hash(complexity, output.hashcode));
+        assert!(Hashcash::verify(&message, complexity, &output));
     }
 
     #[test]
@@ -87,6 +151,87 @@ mod tests {
         let message = "Bonjour monde".to_string();
         let complexity = 14;
         let output = Hashcash::solve(message.clone(), complexity);
-        assert!(check_hash(complexity, output.hashcode));
+        assert!(Hashcash::verify(&message, complexity, &output));
+    }
+
+    #[test]
+    fn test_verify_lowercase_hashcode() {
+        let message = "hello world".to_string();
+        let output = Hashcash::solve(message.clone(), 8);
+        let output = MD5HashCashOutput {
+            seed: output.seed,
+            hashcode: output.hashcode.to_lowercase(),
+        };
+        assert!(Hashcash::verify(&message, 8, &output));
+    }
+
+    #[test]
+    fn test_verify_forged_zero_hashcode() {
+        let output = MD5HashCashOutput {
+            seed: 0,
+            hashcode: "00000000000000000000000000000000".to_string(),
+        };
+        assert!(!Hashcash::verify("hello world", 9, &output));
+    }
+
+    #[test]
+    fn test_verify_hashcode_of_another_seed() {
+        let message = "hello world".to_string();
+        let output = Hashcash::solve(message.clone(), 8);
+        let output = MD5HashCashOutput {
+            seed: output.seed + 1,
+            hashcode: output.hashcode,
+        };
+        assert!(!Hashcash::verify(&message, 8, &output));
+    }
+
+    #[test]
+    fn test_verify_hashcode_of_another_message() {
+        let output = Hashcash::solve("hello world".to_string(), 8);
+        assert!(!Hashcash::verify("hello monde", 8, &output));
+    }
+
+    #[test]
+    fn test_verify_complexity_not_reached() {
+        let seed = 0;
+        let hashcode = HashAlgorithm::MD5.hashcode(seed, "hello world");
+        let output = MD5HashCashOutput { seed, hashcode };
+        assert!(Hashcash::verify("hello world", 0, &output));
+        assert!(!Hashcash::verify("hello world", 128, &output));
+    }
+
+    #[test]
+    fn test_sha_hashcash() {
+        let config = SolverConfig::default();
+        for algorithm in [HashAlgorithm::SHA1, HashAlgorithm::SHA256] {
+            let message = "hello world".to_string();
+            let output = Hashcash::solve_hash(algorithm, message.clone(), 10, &config);
+            assert_eq!(output.hashcode.len(), algorithm.hashcode_length());
+            assert!(Hashcash::verify_hash(algorithm, &message, 10, &output));
+            assert!(!Hashcash::verify(&message, 10, &output));
+        }
+    }
+
+    #[test]
+    fn test_verify_hashcode_of_another_algorithm() {
+        let message = "hello world".to_string();
+        let output = Hashcash::solve(message.clone(), 8);
+        assert!(!Hashcash::verify_hash(
+            HashAlgorithm::SHA1,
+            &message,
+            0,
+            &output
+        ));
+    }
+
+    #[test]
+    fn test_verify_malformed_hashcode() {
+        for hashcode in ["", "00", "not an hexadecimal hashcode !!!!", "-1"] {
+            let output = MD5HashCashOutput {
+                seed: 0,
+                hashcode: hashcode.to_string(),
+            };
+            assert!(!Hashcash::verify("hello world", 0, &output));
+        }
     }
 }
@@ -1,4 +1,6 @@
+pub mod algorithm;
 mod config;
 pub mod dto;
 pub mod hashcash;
+pub mod solver;
 mod utils;
@@ -0,0 +1,287 @@
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::algorithm::HashAlgorithm;
+use crate::config::DEFAULT_SEED_SLICE;
+use crate::dto::{HashCashOutput, MD5HashCashOutput};
+use crate::hashcash::search;
+
+/// Longest wait of a solve before checking its cancel handle again
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    /// Number of worker threads searching seeds
+    pub thread_count: usize,
+    /// Seeds reserved at once by a worker
+    pub seed_slice: u64,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            thread_count: thread::available_parallelism().map_or(1, |count| count.get()),
+            seed_slice: DEFAULT_SEED_SLICE,
+        }
+    }
+}
+
+/// Aborts a running solve from another thread, e.g. when the round ends
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn new() -> CancelHandle {
+        CancelHandle::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    Cancelled,
+    DeadlineExceeded,
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Cancelled => write!(f, "hashcash solving cancelled"),
+            SolveError::DeadlineExceeded => write!(f, "hashcash solving deadline exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+struct Job {
+    algorithm: HashAlgorithm,
+    message: String,
+    complexity: u32,
+    seed_counter: AtomicU64,
+    /// Set once a seed is found or the solve is aborted, workers then wait for the next job
+    stop: AtomicBool,
+}
+
+struct WorkerJob {
+    job: Arc<Job>,
+    result_tx: Sender<HashCashOutput>,
+}
+
+/// Hashcash solver owning a pool of worker threads reused from one challenge to the next
+pub struct HashcashSolver {
+    config: SolverConfig,
+    job_txs: Vec<Sender<WorkerJob>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl HashcashSolver {
+    pub fn new(config: SolverConfig) -> HashcashSolver {
+        let thread_count = config.thread_count.max(1);
+        let mut job_txs = Vec::with_capacity(thread_count);
+        let mut workers = Vec::with_capacity(thread_count);
+        for _ in 0..thread_count {
+            let (job_tx, job_rx) = mpsc::channel::<WorkerJob>();
+            let seed_slice = config.seed_slice;
+            workers.push(thread::spawn(move || {
+                for WorkerJob { job, result_tx } in job_rx {
+                    let output = search(
+                        job.algorithm,
+                        &job.message,
+                        job.complexity,
+                        &job.seed_counter,
+                        seed_slice,
+                        &job.stop,
+                    );
+                    if let Some(output) = output {
+                        job.stop.store(true, Ordering::Relaxed);
+                        let _ = result_tx.send(output);
+                    }
+                }
+            }));
+            job_txs.push(job_tx);
+        }
+        HashcashSolver {
+            config,
+            job_txs,
+            workers,
+        }
+    }
+
+    pub fn config(&self) -> &SolverConfig {
+        &self.config
+    }
+
+    pub fn solve(&self, message: &str, complexity: u32) -> MD5HashCashOutput {
+        self.solve_hash(HashAlgorithm::MD5, message, complexity)
+    }
+
+    pub fn solve_hash(
+        &self,
+        algorithm: HashAlgorithm,
+        message: &str,
+        complexity: u32,
+    ) -> HashCashOutput {
+        self.solve_until(algorithm, message, complexity, None, &CancelHandle::new())
+            .expect("A solve without deadline nor cancellation cannot fail")
+    }
+
+    /// Solves until a seed is found, the deadline is reached or the handle is cancelled
+    pub fn solve_until(
+        &self,
+        algorithm: HashAlgorithm,
+        message: &str,
+        complexity: u32,
+        deadline: Option<Instant>,
+        cancel: &CancelHandle,
+    ) -> Result<HashCashOutput, SolveError> {
+        let job = Arc::new(Job {
+            algorithm,
+            message: message.to_string(),
+            complexity,
+            seed_counter: AtomicU64::new(0),
+            stop: AtomicBool::new(false),
+        });
+        let (result_tx, result_rx) = mpsc::channel();
+        for job_tx in &self.job_txs {
+            job_tx
+                .send(WorkerJob {
+                    job: job.clone(),
+                    result_tx: result_tx.clone(),
+                })
+                .expect("Hashcash worker stopped");
+        }
+        drop(result_tx);
+
+        let result = loop {
+            if cancel.is_cancelled() {
+                break Err(SolveError::Cancelled);
+            }
+            let mut timeout = CANCEL_POLL_INTERVAL;
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    break Err(SolveError::DeadlineExceeded);
+                }
+                timeout = timeout.min(deadline - now);
+            }
+            match result_rx.recv_timeout(timeout) {
+                Ok(output) => break Ok(output),
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => panic!("Every hashcash worker stopped"),
+            }
+        };
+        job.stop.store(true, Ordering::Relaxed);
+        result
+    }
+}
+
+impl Default for HashcashSolver {
+    fn default() -> Self {
+        HashcashSolver::new(SolverConfig::default())
+    }
+}
+
+impl Drop for HashcashSolver {
+    fn drop(&mut self) {
+        self.job_txs.clear();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hashcash::Hashcash;
+
+    use super::*;
+
+    fn new_solver(thread_count: usize) -> HashcashSolver {
+        HashcashSolver::new(SolverConfig {
+            thread_count,
+            seed_slice: 100,
+        })
+    }
+
+    #[test]
+    fn test_solve() {
+        let solver = new_solver(2);
+        let output = solver.solve("hello world", 12);
+        assert!(Hashcash::verify("hello world", 12, &output));
+    }
+
+    #[test]
+    fn test_solve_many_challenges_with_the_same_pool() {
+        let solver = new_solver(3);
+        for i in 0..50 {
+            let message = format!("message {}", i);
+            let output = solver.solve(&message, 8);
+            assert!(Hashcash::verify(&message, 8, &output));
+        }
+    }
+
+    #[test]
+    fn test_solve_every_algorithm_with_the_same_pool() {
+        let solver = new_solver(2);
+        for algorithm in [
+            HashAlgorithm::SHA256,
+            HashAlgorithm::MD5,
+            HashAlgorithm::SHA1,
+        ] {
+            let output = solver.solve_hash(algorithm, "hello world", 8);
+            assert!(Hashcash::verify_hash(algorithm, "hello world", 8, &output));
+        }
+    }
+
+    #[test]
+    fn test_solve_without_thread() {
+        let solver = new_solver(0);
+        let output = solver.solve("hello world", 5);
+        assert!(Hashcash::verify("hello world", 5, &output));
+    }
+
+    #[test]
+    fn test_solve_cancelled() {
+        let solver = new_solver(2);
+        let cancel = CancelHandle::new();
+        let canceller = cancel.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            canceller.cancel();
+        });
+        let result = solver.solve_until(HashAlgorithm::MD5, "hello world", 120, None, &cancel);
+        handle.join().unwrap();
+        assert_eq!(result.unwrap_err(), SolveError::Cancelled);
+
+        let output = solver.solve("hello world", 5);
+        assert!(Hashcash::verify("hello world", 5, &output));
+    }
+
+    #[test]
+    fn test_solve_deadline_exceeded() {
+        let solver = new_solver(2);
+        let start = Instant::now();
+        let deadline = Some(start + Duration::from_millis(30));
+        let result = solver.solve_until(
+            HashAlgorithm::SHA256,
+            "hello world",
+            120,
+            deadline,
+            &CancelHandle::new(),
+        );
+        assert_eq!(result.unwrap_err(), SolveError::DeadlineExceeded);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
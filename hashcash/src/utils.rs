@@ -1,12 +1,97 @@
-pub fn check_hash(mut complexity: u32, hash: String) -> bool {
-    let bit_compare = 1 << 127;
-    let mut sum = u128::from_str_radix(&*hash, 16).unwrap();
-    while complexity > 0 {
-        if (sum & bit_compare) > 0 {
+use crate::algorithm::HashAlgorithm;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Same bytes as `format!("{:016X}", seed)`, written on the stack
+pub fn seed_to_hex(seed: u64) -> [u8; 16] {
+    let mut hex = [0; 16];
+    for (i, digit) in hex.iter_mut().rev().enumerate() {
+        *digit = HEX_DIGITS[((seed >> (4 * i)) & 0xF) as usize];
+    }
+    hex
+}
+
+pub fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in digest {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+pub fn format_hashcode(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Checks that the hashcode is a well formed digest of the algorithm starting with at least
+/// `complexity` zero bits
+pub fn check_hash(algorithm: HashAlgorithm, complexity: u32, hash: &str) -> bool {
+    if hash.len() != algorithm.hashcode_length() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+    let mut bits = 0;
+    for digit in hash.chars().filter_map(|c| c.to_digit(16)) {
+        bits += digit.leading_zeros() - 28;
+        if digit != 0 {
             break;
         }
-        sum = sum << 1;
-        complexity -= 1;
     }
-    complexity == 0
+    bits >= complexity
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::{Md5Hasher, SeedHasher};
+
+    use super::*;
+
+    #[test]
+    fn test_seed_to_hex() {
+        for seed in [0, 1, 0x2a, 0xDEAD_BEEF, u64::MAX] {
+            assert_eq!(seed_to_hex(seed), format!("{:016X}", seed).as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_hash_seed() {
+        let digest = Md5Hasher::hash_seed(844, b"hello");
+        assert_eq!(digest, md5::compute("000000000000034Chello").0);
+        assert_eq!(leading_zero_bits(&digest), 9);
+        assert_eq!(format_hashcode(&digest), "00441745D9BDF8E5D3C7872AC9DBB2C3");
+    }
+
+    #[test]
+    fn test_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0xFF, 0]), 0);
+        assert_eq!(leading_zero_bits(&[0, 0, 0x10]), 19);
+        assert_eq!(leading_zero_bits(&[0; 20]), 160);
+    }
+
+    #[test]
+    fn test_check_hash() {
+        let md5 = HashAlgorithm::MD5;
+        assert!(check_hash(md5, 9, "00441745D9BDF8E5D3C7872AC9DBB2C3"));
+        assert!(!check_hash(md5, 10, "00441745D9BDF8E5D3C7872AC9DBB2C3"));
+        assert!(check_hash(md5, 0, "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"));
+        assert!(check_hash(md5, 128, "00000000000000000000000000000000"));
+        let sha1 = HashAlgorithm::SHA1;
+        assert!(check_hash(sha1, 160, &"0".repeat(40)));
+        assert!(check_hash(sha1, 13, &format!("0004{}", "F".repeat(36))));
+        assert!(!check_hash(sha1, 14, &format!("0004{}", "F".repeat(36))));
+    }
+
+    #[test]
+    fn test_check_hash_malformed() {
+        let md5 = HashAlgorithm::MD5;
+        assert!(!check_hash(md5, 0, ""));
+        assert!(!check_hash(md5, 0, "00"));
+        assert!(!check_hash(md5, 0, "000000000000000000000000000000000"));
+        assert!(!check_hash(md5, 0, "0000000000000000000000000000000G"));
+        assert!(!check_hash(md5, 0, "+0000000000000000000000000000000"));
+        assert!(!check_hash(md5, 0, "0000000000000000000000000000000é"));
+        assert!(!check_hash(HashAlgorithm::SHA256, 0, &"0".repeat(40)));
+    }
 }
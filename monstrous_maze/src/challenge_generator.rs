@@ -1,107 +1,553 @@
-use std::collections::HashSet;
+use std::ops::RangeInclusive;
 
-use log::error;
+use log::{debug, error};
 use rand::seq::SliceRandom;
 use rand::Rng;
 
-use utils::file_utils::read_mazes_file_macro;
-
 use crate::challenge_resolve::{
-    can_go_down, can_go_left, can_go_right, can_go_up, get_monstrous_maze_map_from_input,
-    is_player_on_monster_position,
+    find_shortest_path, move_position, parse_maze_grid, EXIT_TOKEN, FREE_TOKEN, MONSTER_TOKEN,
+    PLAYER_TOKENS,
 };
-use crate::models::{MonstrousMazeInput, MonstrousMazeOutput};
+use crate::models::{Cell, MazeError, MazeGrid, MonstrousMazeInput, MonstrousMazeOutput, Position};
+
+/// Box-drawing glyph of a wall indexed by its wall neighbours: up = 1, down = 2, left = 4, right = 8
+const BOX_DRAWING_WALLS: [char; 16] = [
+    '·', '│', '│', '│', '─', '┘', '┐', '┤', '─', '└', '┌', '├', '─', '┴', '┬', '┼',
+];
+const HASH_WALL: char = '#';
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MazeAlgorithm {
+    /// Long winding corridors with few dead ends
+    RecursiveBacktracker,
+    /// Many short dead ends
+    Kruskal,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MazeStyle {
+    /// `#` walls and an `I` start, like `data/mazes.txt`
+    Hash,
+    /// Box-drawing walls and a `Y` start, like the reference server
+    BoxDrawing,
+}
+
+#[derive(Debug, Clone)]
+pub struct MazeConfig {
+    /// Rooms per row, the rendered grid is `2 * width + 1` characters wide
+    pub width: usize,
+    /// Rooms per column, the rendered grid is `2 * height + 1` lines high
+    pub height: usize,
+    pub monster_count: usize,
+    /// Endurance given to the player, picked at random in this range
+    pub endurance: RangeInclusive<u8>,
+    pub algorithm: MazeAlgorithm,
+    pub style: MazeStyle,
+}
+
+impl Default for MazeConfig {
+    fn default() -> Self {
+        MazeConfig {
+            width: 16,
+            height: 16,
+            monster_count: 10,
+            endurance: 2..=4,
+            algorithm: MazeAlgorithm::RecursiveBacktracker,
+            style: MazeStyle::Hash,
+        }
+    }
+}
+
+impl MazeConfig {
+    /// Config of a difficulty between 0 and 1: from 4x4 rooms and an endurance of 4 up to
+    /// 32x32 rooms and an endurance of 1, with a monster every 25 rooms
+    pub fn from_difficulty(difficulty: f64) -> MazeConfig {
+        let difficulty = difficulty.clamp(0.0, 1.0);
+        let side = 4 + (difficulty * 28.0).round() as usize;
+        let endurance = 4 - (difficulty * 3.0).round() as u8;
+        MazeConfig {
+            width: side,
+            height: side,
+            monster_count: side * side / 25,
+            endurance: endurance..=endurance,
+            ..MazeConfig::default()
+        }
+    }
+}
+
+/// Monster placements tried before falling back on the default maze
+const DIFFICULTY_ATTEMPTS: usize = 10;
+
+/// Maze of a difficulty between 0 and 1, see [`MazeConfig::from_difficulty`]
+pub fn generate_difficulty_maze_challenge<R: Rng>(
+    difficulty: f64,
+    rng: &mut R,
+) -> MonstrousMazeInput {
+    let config = MazeConfig::from_difficulty(difficulty);
+    match (0..DIFFICULTY_ATTEMPTS).find_map(|_| generate_maze_challenge(&config, rng).ok()) {
+        Some(maze) => maze,
+        None => generate_default_maze_challenge(rng),
+    }
+}
 
 pub fn generate_monstrous_maze_challenge() -> MonstrousMazeInput {
-    let mut rng = rand::thread_rng();
-    let mazes: Vec<String> = read_mazes_file_macro()
-        .split('\n')
-        .map(|maze| maze.replace("\\n", "\n"))
+    generate_default_maze_challenge(&mut rand::thread_rng())
+}
+
+pub fn generate_default_maze_challenge<R: Rng>(rng: &mut R) -> MonstrousMazeInput {
+    generate_maze_challenge(&MazeConfig::default(), rng)
+        .expect("The default maze config always gives a solvable maze")
+}
+
+/// Carves a new maze and places its monsters, the maze is only returned once the solver
+/// found a path to the exit with the chosen endurance
+pub fn generate_maze_challenge<R: Rng>(
+    config: &MazeConfig,
+    rng: &mut R,
+) -> Result<MonstrousMazeInput, MazeError> {
+    if config.width == 0 || config.height == 0 {
+        return Err(MazeError::InvalidConfig(format!(
+            "a maze of {}x{} rooms has no room",
+            config.width, config.height
+        )));
+    }
+    if config.endurance.is_empty() || *config.endurance.start() == 0 {
+        return Err(MazeError::InvalidConfig(format!(
+            "endurance range {:?} must be at least 1",
+            config.endurance
+        )));
+    }
+
+    let endurance = rng.gen_range(config.endurance.clone());
+    let mut grid = carve_maze(config.width, config.height, config.algorithm, rng);
+    place_monsters(&mut grid, config.monster_count, endurance, rng)?;
+    let path = find_shortest_path(&grid, endurance).ok_or(MazeError::NoPath)?;
+    debug!(
+        "Generated {:?} maze, endurance {}, path of {} moves",
+        config.algorithm,
+        endurance,
+        path.len()
+    );
+    Ok(MonstrousMazeInput {
+        grid: render_maze(&grid, config.style),
+        endurance,
+    })
+}
+
+/// Carves a perfect maze, every room is reachable by exactly one path. The start is an opening
+/// in the top wall above the first room and the exit one in the bottom wall below the last room.
+pub fn carve_maze<R: Rng>(
+    width: usize,
+    height: usize,
+    algorithm: MazeAlgorithm,
+    rng: &mut R,
+) -> MazeGrid {
+    let grid_width = 2 * width + 1;
+    let grid_height = 2 * height + 1;
+    let mut grid = MazeGrid {
+        cells: vec![Cell::Wall; grid_width * grid_height],
+        width: grid_width,
+        height: grid_height,
+        start: Position { x: 1, y: 0 },
+        exit: Position {
+            x: grid_width - 2,
+            y: grid_height - 1,
+        },
+    };
+    for room in 0..width * height {
+        let index = room_index(&grid, room);
+        grid.cells[index] = Cell::Free;
+    }
+    let (start, exit) = (grid.index(&grid.start), grid.index(&grid.exit));
+    grid.cells[start] = Cell::Free;
+    grid.cells[exit] = Cell::Free;
+
+    match algorithm {
+        MazeAlgorithm::RecursiveBacktracker => carve_backtracker(&mut grid, width, height, rng),
+        MazeAlgorithm::Kruskal => carve_kruskal(&mut grid, width, height, rng),
+    }
+    grid
+}
+
+/// Grid index of a room numbered row by row
+fn room_index(grid: &MazeGrid, room: usize) -> usize {
+    let rooms_per_row = grid.width / 2;
+    grid.index(&Position {
+        x: 2 * (room % rooms_per_row) + 1,
+        y: 2 * (room / rooms_per_row) + 1,
+    })
+}
+
+/// Removes the wall between two neighbouring rooms
+fn open_wall(grid: &mut MazeGrid, room: usize, other_room: usize) {
+    let wall = (room_index(grid, room) + room_index(grid, other_room)) / 2;
+    grid.cells[wall] = Cell::Free;
+}
+
+fn neighbour_rooms(room: usize, width: usize, height: usize) -> Vec<usize> {
+    let (x, y) = (room % width, room / width);
+    let mut rooms = Vec::with_capacity(4);
+    if y > 0 {
+        rooms.push(room - width);
+    }
+    if y + 1 < height {
+        rooms.push(room + width);
+    }
+    if x > 0 {
+        rooms.push(room - 1);
+    }
+    if x + 1 < width {
+        rooms.push(room + 1);
+    }
+    rooms
+}
+
+/// Depth-first carving with an explicit stack, so large mazes cannot overflow the call stack
+fn carve_backtracker<R: Rng>(grid: &mut MazeGrid, width: usize, height: usize, rng: &mut R) {
+    let mut visited = vec![false; width * height];
+    let first_room = rng.gen_range(0..width * height);
+    visited[first_room] = true;
+    let mut stack = vec![first_room];
+    while let Some(&room) = stack.last() {
+        let unvisited: Vec<usize> = neighbour_rooms(room, width, height)
+            .into_iter()
+            .filter(|next| !visited[*next])
+            .collect();
+        match unvisited.choose(rng) {
+            Some(&next) => {
+                open_wall(grid, room, next);
+                visited[next] = true;
+                stack.push(next);
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+}
+
+/// Opens the walls in a random order, skipping those between rooms already connected
+fn carve_kruskal<R: Rng>(grid: &mut MazeGrid, width: usize, height: usize, rng: &mut R) {
+    let mut walls: Vec<(usize, usize)> = (0..width * height)
+        .flat_map(|room| {
+            neighbour_rooms(room, width, height)
+                .into_iter()
+                .filter(move |next| *next > room)
+                .map(move |next| (room, next))
+        })
         .collect();
+    walls.shuffle(rng);
+
+    let mut parents: Vec<usize> = (0..width * height).collect();
+    for (room, next) in walls {
+        let (root, next_root) = (find_root(&mut parents, room), find_root(&mut parents, next));
+        if root != next_root {
+            parents[root] = next_root;
+            open_wall(grid, room, next);
+        }
+    }
+}
 
-    let maze = mazes.choose(&mut rng).expect("Unable to choose maze");
-    MonstrousMazeInput {
-        grid: maze.clone(),
-        endurance: rng.gen_range(2..=4),
+fn find_root(parents: &mut [usize], mut room: usize) -> usize {
+    while parents[room] != room {
+        parents[room] = parents[parents[room]];
+        room = parents[room];
     }
+    room
+}
+
+/// Places monsters on random free cells. A monster landing on the current shortest path is
+/// only kept when the solver still reaches the exit with `endurance`.
+fn place_monsters<R: Rng>(
+    grid: &mut MazeGrid,
+    monster_count: usize,
+    endurance: u8,
+    rng: &mut R,
+) -> Result<(), MazeError> {
+    let (start, exit) = (grid.index(&grid.start), grid.index(&grid.exit));
+    let mut candidates: Vec<usize> = (0..grid.cells.len())
+        .filter(|index| grid.cells[*index] == Cell::Free && *index != start && *index != exit)
+        .collect();
+    candidates.shuffle(rng);
+
+    let mut path = find_shortest_path(grid, endurance).ok_or(MazeError::NoPath)?;
+    let mut on_path = path_cells(grid, &path);
+    let mut placed = 0;
+    for index in candidates {
+        if placed == monster_count {
+            break;
+        }
+        grid.cells[index] = Cell::Monster;
+        if on_path[index] {
+            match find_shortest_path(grid, endurance) {
+                Some(new_path) => path = new_path,
+                None => {
+                    grid.cells[index] = Cell::Free;
+                    continue;
+                }
+            }
+            on_path = path_cells(grid, &path);
+        }
+        placed += 1;
+    }
+
+    if placed < monster_count {
+        return Err(MazeError::TooManyMonsters {
+            requested: monster_count,
+            placed,
+        });
+    }
+    Ok(())
+}
+
+/// Marks the cells walked through by a valid path
+fn path_cells(grid: &MazeGrid, path: &str) -> Vec<bool> {
+    let mut cells = vec![false; grid.cells.len()];
+    let mut position = grid.start.clone();
+    cells[grid.index(&position)] = true;
+    for direction in path.chars() {
+        position = move_position(&position, direction).expect("The solver path stays in the maze");
+        cells[grid.index(&position)] = true;
+    }
+    cells
+}
+
+/// Renders a grid in the text format read by [`parse_maze_grid`]
+pub fn render_maze(grid: &MazeGrid, style: MazeStyle) -> String {
+    let is_wall = |x: usize, y: usize| {
+        x < grid.width && y < grid.height && grid.cells[y * grid.width + x] == Cell::Wall
+    };
+    let start_token = match style {
+        MazeStyle::Hash => PLAYER_TOKENS[0],
+        MazeStyle::BoxDrawing => PLAYER_TOKENS[1],
+    };
+
+    let mut rows = Vec::with_capacity(grid.height);
+    for y in 0..grid.height {
+        let mut row = String::with_capacity(grid.width);
+        for x in 0..grid.width {
+            let position = Position { x, y };
+            let token = match grid.cell(&position) {
+                Cell::Monster => MONSTER_TOKEN,
+                Cell::Free if position == grid.start => start_token,
+                Cell::Free if position == grid.exit => EXIT_TOKEN,
+                Cell::Free => FREE_TOKEN,
+                Cell::Wall if style == MazeStyle::Hash => HASH_WALL,
+                Cell::Wall => {
+                    let neighbours = [
+                        y > 0 && is_wall(x, y - 1),
+                        is_wall(x, y + 1),
+                        x > 0 && is_wall(x - 1, y),
+                        is_wall(x + 1, y),
+                    ];
+                    let mask = neighbours
+                        .iter()
+                        .enumerate()
+                        .fold(0, |mask, (bit, is_wall)| mask | (*is_wall as usize) << bit);
+                    BOX_DRAWING_WALLS[mask]
+                }
+            };
+            row.push(token);
+        }
+        rows.push(row);
+    }
+    rows.join("\n")
 }
 
 pub fn validate_maze_challenge(
     challenge_input: &MonstrousMazeInput,
     challenge_output: &MonstrousMazeOutput,
 ) -> bool {
-    let mut maze_map = get_monstrous_maze_map_from_input(challenge_input);
+    let grid = match parse_maze_grid(&challenge_input.grid) {
+        Ok(grid) => grid,
+        Err(err) => {
+            error!("Invalid maze: {}", err);
+            return false;
+        }
+    };
+    let mut position = grid.start.clone();
     let mut endurance_left = challenge_input.endurance;
-    let empty_hashset = HashSet::new();
     for direction in challenge_output.path.chars() {
-        match direction {
-            '^' => {
-                if can_go_up(&maze_map, &empty_hashset) {
-                    maze_map.player_position.y -= 1;
-                } else {
-                    error!("Unable to go up");
-                    return false;
-                }
-            }
-            'v' => {
-                if can_go_down(&maze_map, &empty_hashset) {
-                    maze_map.player_position.y += 1;
-                } else {
-                    error!("Unable to go down");
-                    return false;
-                }
-            }
-            '<' => {
-                if can_go_left(&maze_map, &empty_hashset) {
-                    maze_map.player_position.x -= 1;
-                } else {
-                    error!("Unable to go left");
-                    return false;
-                }
-            }
-            '>' => {
-                if can_go_right(&maze_map, &empty_hashset) {
-                    maze_map.player_position.x += 1;
-                } else {
-                    error!("Unable to go right");
-                    return false;
-                }
-            }
-            _ => {
-                error!("Invalid direction");
+        position = match move_position(&position, direction)
+            .filter(|next| grid.cell(next) != Cell::Wall)
+        {
+            Some(next) => next,
+            None => {
+                error!("Unable to go {:?}", direction);
                 return false;
             }
-        }
-        if is_player_on_monster_position(&maze_map) {
-            endurance_left -= 1;
+        };
+        if grid.cell(&position) == Cell::Monster {
+            endurance_left = endurance_left.saturating_sub(1);
         }
 
-        if endurance_left <= 0 {
+        if endurance_left == 0 {
             error!("Endurance is 0");
             return false;
         }
     }
-    maze_map.player_position == maze_map.target_position
+    position == grid.exit
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::VecDeque;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     use utils::file_utils::read_file;
 
-    use crate::challenge_generator::{generate_monstrous_maze_challenge, validate_maze_challenge};
-    use crate::challenge_resolve::{get_monstrous_maze_map_from_input, MonstrousMazeResolver};
+    use crate::challenge_generator::*;
+    use crate::challenge_resolve::{parse_maze_grid, MonstrousMazeResolver};
     use crate::models::{MonstrousMazeInput, MonstrousMazeOutput};
 
+    const ALGORITHMS: [MazeAlgorithm; 2] =
+        [MazeAlgorithm::RecursiveBacktracker, MazeAlgorithm::Kruskal];
+    const STYLES: [MazeStyle; 2] = [MazeStyle::Hash, MazeStyle::BoxDrawing];
+
+    fn reachable_cells(grid: &MazeGrid) -> usize {
+        let mut visited = vec![false; grid.cells.len()];
+        visited[grid.index(&grid.start)] = true;
+        let mut queue = VecDeque::from([grid.start.clone()]);
+        let mut count = 0;
+        while let Some(position) = queue.pop_front() {
+            count += 1;
+            for direction in ['^', 'v', '<', '>'] {
+                match move_position(&position, direction) {
+                    Some(next) if grid.cell(&next) != Cell::Wall && !visited[grid.index(&next)] => {
+                        visited[grid.index(&next)] = true;
+                        queue.push_back(next);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        count
+    }
+
     #[test]
     fn test_generate_monstrous_maze_challenge() {
         for _ in 0..10 {
             let challenge_input = generate_monstrous_maze_challenge();
-            assert!(challenge_input.grid.len() > 0);
+            assert!(!challenge_input.grid.is_empty());
             assert!(challenge_input.endurance > 0);
-            get_monstrous_maze_map_from_input(&challenge_input);
+            parse_maze_grid(&challenge_input.grid).unwrap();
         }
     }
 
+    #[test]
+    fn test_generate_maze_challenge_is_solvable() {
+        let mut rng = StdRng::seed_from_u64(12);
+        for algorithm in ALGORITHMS {
+            for style in STYLES {
+                let config = MazeConfig {
+                    width: 12,
+                    height: 8,
+                    monster_count: 15,
+                    endurance: 1..=3,
+                    algorithm,
+                    style,
+                };
+                for _ in 0..20 {
+                    let input = generate_maze_challenge(&config, &mut rng).unwrap();
+                    let grid = parse_maze_grid(&input.grid).unwrap();
+                    assert_eq!((grid.width, grid.height), (25, 17));
+                    let monsters = grid.cells.iter().filter(|cell| **cell == Cell::Monster);
+                    assert_eq!(monsters.count(), 15);
+                    let output =
+                        MonstrousMazeResolver::resolve_monstrous_maze_challenge(&input).unwrap();
+                    assert!(validate_maze_challenge(&input, &output));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_difficulty_maze_challenge() {
+        let easiest = MazeConfig::from_difficulty(0.0);
+        assert_eq!((easiest.width, easiest.endurance), (4, 4..=4));
+        let hardest = MazeConfig::from_difficulty(1.5);
+        assert_eq!((hardest.width, hardest.endurance), (32, 1..=1));
+        for difficulty in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let input = generate_difficulty_maze_challenge(difficulty, &mut rand::thread_rng());
+            let output = MonstrousMazeResolver::resolve_monstrous_maze_challenge(&input).unwrap();
+            assert!(validate_maze_challenge(&input, &output));
+        }
+    }
+
+    #[test]
+    fn test_carve_maze_is_perfect() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for algorithm in ALGORITHMS {
+            let grid = carve_maze(10, 7, algorithm, &mut rng);
+            let free_cells = grid.cells.iter().filter(|cell| **cell == Cell::Free);
+            // 70 rooms, the 69 openings of a spanning tree, the start and the exit
+            assert_eq!(free_cells.count(), 70 + 69 + 2);
+            assert_eq!(reachable_cells(&grid), 70 + 69 + 2);
+        }
+    }
+
+    #[test]
+    fn test_render_maze() {
+        let grid = carve_maze(1, 1, MazeAlgorithm::Kruskal, &mut StdRng::seed_from_u64(0));
+        assert_eq!(render_maze(&grid, MazeStyle::Hash), "#I#\n# #\n#X#");
+        assert_eq!(render_maze(&grid, MazeStyle::BoxDrawing), "│Y│\n│ │\n│X│");
+
+        let mut grid = carve_maze(2, 1, MazeAlgorithm::Kruskal, &mut StdRng::seed_from_u64(0));
+        grid.cells[6] = Cell::Monster;
+        assert_eq!(
+            render_maze(&grid, MazeStyle::BoxDrawing),
+            "│Y──┐\n│M  │\n└──X│"
+        );
+    }
+
+    #[test]
+    fn test_render_then_parse_maze() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for style in STYLES {
+            let mut grid = carve_maze(9, 6, MazeAlgorithm::RecursiveBacktracker, &mut rng);
+            place_monsters(&mut grid, 5, 2, &mut rng).unwrap();
+            assert_eq!(parse_maze_grid(&render_maze(&grid, style)), Ok(grid));
+        }
+    }
+
+    #[test]
+    fn test_generate_maze_challenge_too_many_monsters() {
+        let config = MazeConfig {
+            width: 2,
+            height: 2,
+            monster_count: 8,
+            endurance: 1..=1,
+            ..MazeConfig::default()
+        };
+        let result = generate_maze_challenge(&config, &mut StdRng::seed_from_u64(0));
+        assert!(matches!(
+            result,
+            Err(MazeError::TooManyMonsters { requested: 8, placed }) if placed < 8
+        ));
+    }
+
+    #[test]
+    fn test_generate_maze_challenge_invalid_config() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let config = MazeConfig {
+            width: 0,
+            ..MazeConfig::default()
+        };
+        assert!(matches!(
+            generate_maze_challenge(&config, &mut rng),
+            Err(MazeError::InvalidConfig(_))
+        ));
+        let config = MazeConfig {
+            endurance: 0..=2,
+            ..MazeConfig::default()
+        };
+        assert!(matches!(
+            generate_maze_challenge(&config, &mut rng),
+            Err(MazeError::InvalidConfig(_))
+        ));
+    }
+
     #[test]
     fn test_validate_maze_challenge() {
         let mazes: Vec<String> = read_file("data/mazes.txt")
@@ -114,13 +560,10 @@ mod tests {
                 endurance: 2,
                 grid: maze.to_string(),
             };
-            assert_eq!(
-                validate_maze_challenge(
-                    &input,
-                    &MonstrousMazeResolver::resolve_monstrous_maze_challenge(&input)
-                ),
-                true
-            );
+            assert!(validate_maze_challenge(
+                &input,
+                &MonstrousMazeResolver::resolve_monstrous_maze_challenge(&input).unwrap()
+            ));
         })
     }
 
@@ -134,24 +577,24 @@ mod tests {
             path: "^>v<".to_string(),
         };
 
-        assert_eq!(validate_maze_challenge(&input, &output), false);
+        assert!(!validate_maze_challenge(&input, &output));
 
         let output = MonstrousMazeOutput {
             path: "vvvvv".to_string(),
         };
 
-        assert_eq!(validate_maze_challenge(&input, &output), false);
+        assert!(!validate_maze_challenge(&input, &output));
 
         let output = MonstrousMazeOutput {
             path: "^>v<^>v<".to_string(),
         };
 
-        assert_eq!(validate_maze_challenge(&input, &output), false);
+        assert!(!validate_maze_challenge(&input, &output));
 
         let output = MonstrousMazeOutput {
             path: "v>v<^>v<^>v<".to_string(),
         };
 
-        assert_eq!(validate_maze_challenge(&input, &output), false);
+        assert!(!validate_maze_challenge(&input, &output));
     }
 }
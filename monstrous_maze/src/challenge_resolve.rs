@@ -1,335 +1,159 @@
-use std::collections::HashSet;
+use std::collections::VecDeque;
 
 use log::debug;
 
-use crate::models::{MonstrousMazeInput, MonstrousMazeMap, MonstrousMazeOutput, Position};
+use crate::models::{Cell, MazeError, MazeGrid, MonstrousMazeInput, MonstrousMazeOutput, Position};
 
 pub struct MonstrousMazeResolver;
 
-static PLAYER_TOKEN: char = 'I';
-static EXIT_TOKEN: char = 'X';
-// static FREE_TOKEN: char = ' ';
-static WALL_TOKEN: char = '#';
-static MONSTER_TOKEN: char = 'M';
+/// Start tokens, `Y` is used by the reference server
+pub static PLAYER_TOKENS: [char; 2] = ['I', 'Y'];
+pub static EXIT_TOKEN: char = 'X';
+pub static FREE_TOKEN: char = ' ';
+pub static MONSTER_TOKEN: char = 'M';
+
+/// Moves tried from every cell, in this order
+const MOVES: [(char, isize, isize); 4] = [('^', 0, -1), ('v', 0, 1), ('<', -1, 0), ('>', 1, 0)];
 
 impl MonstrousMazeResolver {
     pub fn resolve_monstrous_maze_challenge(
         monstrous_maze_input: &MonstrousMazeInput,
-    ) -> MonstrousMazeOutput {
+    ) -> Result<MonstrousMazeOutput, MazeError> {
         debug!("{:?}", monstrous_maze_input.grid);
-        let endurance_left = monstrous_maze_input.endurance;
-        let map = get_monstrous_maze_map_from_input(monstrous_maze_input);
-
-        let path = find_path(&map, &endurance_left, &String::new(), &HashSet::new());
-
-        if path.is_some() {
-            return MonstrousMazeOutput {
-                path: path.unwrap(),
-            };
-        }
-        panic!("No path found");
+        let grid = parse_maze_grid(&monstrous_maze_input.grid)?;
+        find_shortest_path(&grid, monstrous_maze_input.endurance)
+            .map(|path| MonstrousMazeOutput { path })
+            .ok_or(MazeError::NoPath)
     }
 }
 
-fn find_path(
-    map: &MonstrousMazeMap,
-    endurance_left: &u8,
-    current_path: &String,
-    already_visited: &HashSet<Position>,
-) -> Option<String> {
-    if map.player_position == map.target_position {
-        return Some(current_path.clone());
+/// Breadth-first search over `(position, endurance left)` states: a cell can be worth visiting
+/// again with more endurance left. Returns the shortest path, `None` if the exit is out of reach.
+pub fn find_shortest_path(grid: &MazeGrid, endurance: u8) -> Option<String> {
+    if grid.start == grid.exit {
+        return Some(String::new());
     }
-
-    let mut new_endurance_left = endurance_left.clone();
-
-    if is_player_on_monster_position(map) {
-        new_endurance_left -= 1;
-        debug!(
-            "Player is on monster position, endurance left: {}",
-            new_endurance_left
-        );
-    }
-
-    if new_endurance_left == 0 {
-        debug!("Endurance is 0, wrong path");
+    if endurance == 0 {
         return None;
     }
 
-    let mut new_already_visited = already_visited.clone();
-    new_already_visited.insert(map.player_position.clone());
-
-    if let Some(path) = process_up_path(
-        map,
-        &new_endurance_left,
-        &current_path,
-        &new_already_visited,
-    ) {
-        return Some(path);
-    }
-
-    if let Some(path) = process_down_path(
-        map,
-        &new_endurance_left,
-        &current_path,
-        &new_already_visited,
-    ) {
-        return Some(path);
-    }
-
-    if let Some(path) = process_left_path(
-        map,
-        &new_endurance_left,
-        &current_path,
-        &new_already_visited,
-    ) {
-        return Some(path);
-    }
-
-    if let Some(path) = process_right_path(
-        map,
-        &new_endurance_left,
-        &current_path,
-        &new_already_visited,
-    ) {
-        return Some(path);
-    }
-
-    None
-}
-
-fn process_right_path(
-    map: &MonstrousMazeMap,
-    endurance_left: &u8,
-    current_path: &String,
-    already_visited: &HashSet<Position>,
-) -> Option<String> {
-    if can_go_right(map, already_visited) {
-        let mut new_map = map.clone();
-        let mut new_path = current_path.clone();
-        new_map.player_position.x += 1;
-        new_path.push_str(">");
-
-        return find_path(&new_map, endurance_left, &new_path, &already_visited);
+    let levels = endurance as usize + 1;
+    let state = |index: usize, endurance_left: u8| index * levels + endurance_left as usize;
+    let mut visited = vec![false; grid.cells.len() * levels];
+    let mut previous: Vec<Option<(usize, char)>> = vec![None; grid.cells.len() * levels];
+    let start = grid.index(&grid.start);
+    visited[state(start, endurance)] = true;
+    let mut queue = VecDeque::from([(start, endurance)]);
+
+    while let Some((index, endurance_left)) = queue.pop_front() {
+        let position = grid.position(index);
+        for (direction, _, _) in MOVES {
+            let next = match move_position(&position, direction) {
+                Some(next) => next,
+                None => continue,
+            };
+            let next_endurance_left = match grid.cell(&next) {
+                Cell::Wall => continue,
+                Cell::Free => endurance_left,
+                Cell::Monster => endurance_left - 1,
+            };
+            if next_endurance_left == 0 {
+                continue;
+            }
+            let next_index = grid.index(&next);
+            let next_state = state(next_index, next_endurance_left);
+            if visited[next_state] {
+                continue;
+            }
+            visited[next_state] = true;
+            previous[next_state] = Some((state(index, endurance_left), direction));
+            if next == grid.exit {
+                return Some(rebuild_path(&previous, next_state));
+            }
+            queue.push_back((next_index, next_endurance_left));
+        }
     }
-
     None
 }
 
-fn process_left_path(
-    map: &MonstrousMazeMap,
-    endurance_left: &u8,
-    current_path: &String,
-    already_visited: &HashSet<Position>,
-) -> Option<String> {
-    if can_go_left(map, already_visited) {
-        let mut new_map = map.clone();
-        let mut new_path = current_path.clone();
-        new_map.player_position.x -= 1;
-        new_path.push_str("<");
-
-        return find_path(&new_map, endurance_left, &new_path, &already_visited);
-    }
-
-    None
+/// Position after one move in `direction`, `None` for an unknown direction or when leaving the grid
+pub fn move_position(position: &Position, direction: char) -> Option<Position> {
+    let (_, dx, dy) = MOVES.iter().find(|(token, _, _)| *token == direction)?;
+    Some(Position {
+        x: position.x.checked_add_signed(*dx)?,
+        y: position.y.checked_add_signed(*dy)?,
+    })
 }
 
-fn process_up_path(
-    map: &MonstrousMazeMap,
-    endurance_left: &u8,
-    current_path: &String,
-    already_visited: &HashSet<Position>,
-) -> Option<String> {
-    if can_go_up(map, already_visited) {
-        let mut new_map = map.clone();
-        let mut new_path = current_path.clone();
-        new_map.player_position.y -= 1;
-        new_path.push_str("^");
-
-        return find_path(&new_map, endurance_left, &new_path, &already_visited);
+fn rebuild_path(previous: &[Option<(usize, char)>], mut state: usize) -> String {
+    let mut directions = Vec::new();
+    while let Some((previous_state, direction)) = previous[state] {
+        directions.push(direction);
+        state = previous_state;
     }
-
-    None
+    directions.iter().rev().collect()
 }
 
-fn process_down_path(
-    map: &MonstrousMazeMap,
-    endurance_left: &u8,
-    current_path: &String,
-    already_visited: &HashSet<Position>,
-) -> Option<String> {
-    if can_go_down(map, already_visited) {
-        let mut new_map = map.clone();
-        let mut new_path = current_path.clone();
-        new_map.player_position.y += 1;
-        new_path.push_str("v");
-
-        return find_path(&new_map, endurance_left, &new_path, &already_visited);
+/// Parses a grid char by char: spaces are free, `M` are monsters, `X` is the exit, `I` or `Y`
+/// the start and any other symbol (`#`, box-drawing characters...) is a wall.
+/// Lines may have different lengths, missing cells are walls.
+pub fn parse_maze_grid(grid: &str) -> Result<MazeGrid, MazeError> {
+    let rows: Vec<Vec<char>> = grid.split('\n').map(|row| row.chars().collect()).collect();
+    let height = rows.len();
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut cells = vec![Cell::Wall; width * height];
+    let mut start = None;
+    let mut exit = None;
+    for (y, row) in rows.iter().enumerate() {
+        for (x, token) in row.iter().enumerate() {
+            let position = Position { x, y };
+            cells[y * width + x] = if *token == MONSTER_TOKEN {
+                Cell::Monster
+            } else if *token == FREE_TOKEN {
+                Cell::Free
+            } else if *token == EXIT_TOKEN {
+                exit.get_or_insert(position);
+                Cell::Free
+            } else if PLAYER_TOKENS.contains(token) {
+                start.get_or_insert(position);
+                Cell::Free
+            } else {
+                Cell::Wall
+            };
+        }
     }
-
-    None
-}
-
-pub fn get_monstrous_maze_map_from_input(
-    monstrous_maze_input: &MonstrousMazeInput,
-) -> MonstrousMazeMap {
-    let map: Vec<String> = monstrous_maze_input
-        .grid
-        .split("\n")
-        .map(|x| x.to_string())
-        .collect();
-    assert!(map.len() > 0);
-
-    let map_height = map.len();
-    let map_width = map[0].len();
-    let player_position = find_player_position_from_vec_map(&map);
-    let target_position = find_exit_position_from_vec_map(&map);
+    let grid = MazeGrid {
+        cells,
+        width,
+        height,
+        start: start.ok_or(MazeError::MissingStart)?,
+        exit: exit.ok_or(MazeError::MissingExit)?,
+    };
     debug!(
         "Map height : {}, Map width : {}, player : {:?}, target : {:?}",
-        map_height, map_width, player_position, target_position
+        grid.height, grid.width, grid.start, grid.exit
     );
-
-    MonstrousMazeMap {
-        map,
-        map_height,
-        map_width,
-        player_position,
-        target_position,
-    }
-}
-
-fn find_player_position_from_vec_map(map: &Vec<String>) -> Position {
-    let player_position = find_char_position_in_vec_map(&PLAYER_TOKEN, map);
-    if player_position.is_some() {
-        return player_position.unwrap();
-    }
-    panic!("Player not found");
-}
-
-fn find_exit_position_from_vec_map(map: &Vec<String>) -> Position {
-    let exit_position = find_char_position_in_vec_map(&EXIT_TOKEN, map);
-    if exit_position.is_some() {
-        return exit_position.unwrap();
-    }
-    panic!("Exit not found");
-}
-
-fn find_char_position_in_vec_map(char: &char, map: &Vec<String>) -> Option<Position> {
-    for (y, row) in map.iter().enumerate() {
-        for (x, cell) in row.chars().enumerate() {
-            if cell == *char {
-                return Some(Position { x, y });
-            }
-        }
-    }
-    None
-}
-
-pub fn can_go_up(map: &MonstrousMazeMap, already_visited: &HashSet<Position>) -> bool {
-    let player_position: &Position = &map.player_position;
-    if player_position.y == 0
-        || is_wall(
-            &map.map[player_position.y - 1]
-                .chars()
-                .nth(player_position.x)
-                .expect("Player position is out of bounds"),
-        )
-        || already_visited.contains(&Position {
-            y: player_position.y - 1,
-            x: player_position.x,
-        })
-    {
-        return false;
-    }
-
-    true
-}
-
-pub fn can_go_down(map: &MonstrousMazeMap, already_visited: &HashSet<Position>) -> bool {
-    let player_position: &Position = &map.player_position;
-    if player_position.y == map.map_height - 1
-        || is_wall(
-            &map.map[player_position.y + 1]
-                .chars()
-                .nth(player_position.x)
-                .expect("Player position is out of bounds"),
-        )
-        || already_visited.contains(&Position {
-            y: player_position.y + 1,
-            x: player_position.x,
-        })
-    {
-        return false;
-    }
-
-    true
-}
-
-pub fn can_go_right(map: &MonstrousMazeMap, already_visited: &HashSet<Position>) -> bool {
-    let player_position: &Position = &map.player_position;
-    if player_position.x == map.map_width - 1
-        || is_wall(
-            &map.map[player_position.y]
-                .chars()
-                .nth(player_position.x + 1)
-                .expect("Player position is out of bounds"),
-        )
-        || already_visited.contains(&Position {
-            y: player_position.y,
-            x: player_position.x + 1,
-        })
-    {
-        return false;
-    }
-
-    true
-}
-
-pub fn can_go_left(map: &MonstrousMazeMap, already_visited: &HashSet<Position>) -> bool {
-    let player_position: &Position = &map.player_position;
-    if player_position.x == 0
-        || is_wall(
-            &map.map[player_position.y]
-                .chars()
-                .nth(player_position.x - 1)
-                .expect("Player position is out of bounds"),
-        )
-        || already_visited.contains(&Position {
-            y: player_position.y,
-            x: player_position.x - 1,
-        })
-    {
-        return false;
-    }
-
-    true
-}
-
-fn is_wall(char: &char) -> bool {
-    *char == WALL_TOKEN
-}
-
-pub fn is_player_on_monster_position(map: &MonstrousMazeMap) -> bool {
-    return map.map[map.player_position.y]
-        .chars()
-        .nth(map.player_position.x)
-        .expect("Player position is out of bounds")
-        == MONSTER_TOKEN;
+    Ok(grid)
 }
 
 // test module
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
-
     use utils::file_utils::read_file;
 
-    use crate::challenge_resolve::{
-        can_go_down, can_go_left, can_go_right, can_go_up, find_char_position_in_vec_map,
-        find_exit_position_from_vec_map, find_player_position_from_vec_map,
-        get_monstrous_maze_map_from_input, is_player_on_monster_position, MonstrousMazeResolver,
+    use crate::challenge_generator::validate_maze_challenge;
+    use crate::challenge_resolve::{move_position, parse_maze_grid, MonstrousMazeResolver};
+    use crate::models::{
+        Cell, MazeError, MazeGrid, MonstrousMazeInput, MonstrousMazeOutput, Position,
     };
-    use crate::models::{MonstrousMazeInput, MonstrousMazeMap, Position};
+
+    fn solve(grid: &str, endurance: u8) -> Result<String, MazeError> {
+        MonstrousMazeResolver::resolve_monstrous_maze_challenge(&MonstrousMazeInput {
+            endurance,
+            grid: grid.to_string(),
+        })
+        .map(|output| output.path)
+    }
 
     #[test]
     fn test_mazes_resolution() {
@@ -348,233 +172,160 @@ mod tests {
                 MonstrousMazeResolver::resolve_monstrous_maze_challenge(&MonstrousMazeInput {
                     endurance: 2,
                     grid: maze.to_string(),
-                });
+                })
+                .unwrap();
             assert_eq!(answer.path, answers[index]);
         })
     }
 
     #[test]
-    fn test_is_player_on_monster_position() {
-        let mut monstrous_maze_map = MonstrousMazeMap {
-            map: vec![
-                "# ###".to_string(),
-                "#   #".to_string(),
-                "# M #".to_string(),
-                "#   #".to_string(),
-                "#####".to_string(),
-            ],
-            map_height: 5,
-            map_width: 5,
-            player_position: Position { x: 1, y: 0 },
-            target_position: Position { x: 0, y: 0 },
+    fn test_shortest_path() {
+        let grid = "#I####\n#    #\n# ## #\n#    #\n####X#";
+        let path = solve(grid, 1).unwrap();
+        assert_eq!(path.len(), 7);
+        let input = MonstrousMazeInput {
+            endurance: 1,
+            grid: grid.to_string(),
         };
-        assert_eq!(is_player_on_monster_position(&monstrous_maze_map), false);
-
-        monstrous_maze_map.player_position = Position { x: 2, y: 2 };
-        assert_eq!(is_player_on_monster_position(&monstrous_maze_map), true);
+        assert!(validate_maze_challenge(
+            &input,
+            &MonstrousMazeOutput { path }
+        ));
     }
 
     #[test]
-    fn test_can_go_left() {
-        let mut monstrous_maze_map = MonstrousMazeMap {
-            map: vec![
-                "# ###".to_string(),
-                "#   #".to_string(),
-                "# M #".to_string(),
-                "#   #".to_string(),
-                "#####".to_string(),
-            ],
-            map_height: 5,
-            map_width: 5,
-            player_position: Position { x: 1, y: 0 },
-            target_position: Position { x: 0, y: 0 },
-        };
-        let mut already_visited: HashSet<Position> = HashSet::new();
-        assert_eq!(can_go_left(&monstrous_maze_map, &already_visited), false);
-
-        monstrous_maze_map.player_position = Position { x: 2, y: 1 };
-        assert_eq!(can_go_left(&monstrous_maze_map, &already_visited), true);
-
-        already_visited.insert(Position { x: 1, y: 1 });
-        assert_eq!(can_go_left(&monstrous_maze_map, &already_visited), false);
+    fn test_shortest_path_depends_on_endurance() {
+        let grid = "#######\nI  M  X\n# ### #\n#     #\n#######";
+        assert_eq!(solve(grid, 1), Ok(">vv>>>>^^>".to_string()));
+        assert_eq!(solve(grid, 2), Ok(">>>>>>".to_string()));
     }
 
     #[test]
-    fn test_can_go_up() {
-        let mut monstrous_maze_map = MonstrousMazeMap {
-            map: vec![
-                "# ###".to_string(),
-                "#   #".to_string(),
-                "# M #".to_string(),
-                "#   #".to_string(),
-                "#####".to_string(),
-            ],
-            map_height: 5,
-            map_width: 5,
-            player_position: Position { x: 1, y: 0 },
-            target_position: Position { x: 0, y: 0 },
-        };
-        let mut already_visited: HashSet<Position> = HashSet::new();
-        assert_eq!(can_go_up(&monstrous_maze_map, &already_visited), false);
-
-        monstrous_maze_map.player_position = Position { x: 1, y: 2 };
-        assert_eq!(can_go_up(&monstrous_maze_map, &already_visited), true);
-
-        already_visited.insert(Position { x: 1, y: 1 });
-        assert_eq!(can_go_up(&monstrous_maze_map, &already_visited), false);
+    fn test_no_path() {
+        assert_eq!(solve("IMX", 0), Err(MazeError::NoPath));
+        assert_eq!(solve("IMX", 1), Err(MazeError::NoPath));
+        assert_eq!(solve("IMX", 2), Ok(">>".to_string()));
+        assert_eq!(solve("I#X", 4), Err(MazeError::NoPath));
     }
 
     #[test]
-    fn test_can_go_right() {
-        let mut monstrous_maze_map = MonstrousMazeMap {
-            map: vec![
-                "# ###".to_string(),
-                "#   #".to_string(),
-                "# M #".to_string(),
-                "#   #".to_string(),
-                "#####".to_string(),
-            ],
-            map_height: 5,
-            map_width: 5,
-            player_position: Position { x: 1, y: 0 },
-            target_position: Position { x: 0, y: 0 },
+    fn test_large_maze_does_not_overflow_the_stack() {
+        let size = 301;
+        let mut rows: Vec<String> = (0..size)
+            .map(|y| match y % 4 {
+                1 => "#".repeat(size - 1) + " ",
+                3 => " ".to_string() + &"#".repeat(size - 1),
+                _ => " ".repeat(size),
+            })
+            .collect();
+        rows[0].replace_range(0..1, "I");
+        rows[size - 1].replace_range(size - 1..size, "X");
+        let input = MonstrousMazeInput {
+            endurance: 1,
+            grid: rows.join("\n"),
         };
-        let mut already_visited: HashSet<Position> = HashSet::new();
-        assert_eq!(can_go_right(&monstrous_maze_map, &already_visited), false);
 
-        monstrous_maze_map.player_position = Position { x: 2, y: 1 };
-        assert_eq!(can_go_right(&monstrous_maze_map, &already_visited), true);
-
-        already_visited.insert(Position { x: 3, y: 1 });
-        assert_eq!(can_go_right(&monstrous_maze_map, &already_visited), false);
+        let output = MonstrousMazeResolver::resolve_monstrous_maze_challenge(&input).unwrap();
+        assert_eq!(output.path.len(), (size - 1) * (size / 2 + 1) + size - 1);
+        assert!(validate_maze_challenge(&input, &output));
     }
 
     #[test]
-    fn test_can_go_down() {
-        let mut monstrous_maze_map = MonstrousMazeMap {
-            map: vec![
-                "# ###".to_string(),
-                "#   #".to_string(),
-                "# M #".to_string(),
-                "#   #".to_string(),
-                "#####".to_string(),
-            ],
-            map_height: 5,
-            map_width: 5,
-            player_position: Position { x: 1, y: 3 },
-            target_position: Position { x: 0, y: 0 },
-        };
-        let mut already_visited: HashSet<Position> = HashSet::new();
-        assert_eq!(can_go_down(&monstrous_maze_map, &already_visited), false);
-
-        monstrous_maze_map.player_position = Position { x: 2, y: 1 };
-        assert_eq!(can_go_down(&monstrous_maze_map, &already_visited), true);
-
-        already_visited.insert(Position { x: 2, y: 2 });
-        assert_eq!(can_go_down(&monstrous_maze_map, &already_visited), false);
+    fn test_box_drawing_maze_resolution() {
+        let grid = read_file("data/box_drawing_maze.txt");
+        let input = MonstrousMazeInput { endurance: 2, grid };
+        let output = MonstrousMazeResolver::resolve_monstrous_maze_challenge(&input).unwrap();
+        assert!(validate_maze_challenge(&input, &output));
     }
 
     #[test]
-    fn test_find_char_position_in_vec_map() {
-        let map = vec![
-            "# ###".to_string(),
-            "#   #".to_string(),
-            "# M #".to_string(),
-            "#   #".to_string(),
-            "#####".to_string(),
-        ];
-
-        let result = find_char_position_in_vec_map(&'M', &map);
-        assert!(result.is_some());
-        assert_eq!(result.unwrap(), Position { x: 2, y: 2 });
-
-        let result = find_char_position_in_vec_map(&'I', &map);
-        assert!(result.is_none());
+    fn test_box_drawing_walls() {
+        let grid = "┌Y──┬──┐\n│ M │  │\n├─ ─┘ ─┤\n│      X\n└──────┘";
+        assert_eq!(solve(grid, 1), Err(MazeError::NoPath));
+        assert_eq!(solve(grid, 2), Ok("v>vv>>>>>".to_string()));
     }
 
     #[test]
-    fn test_find_exit_position_from_vec_map() {
-        let map = vec![
-            "# ###".to_string(),
-            "#   #".to_string(),
-            "# M #".to_string(),
-            "#   #".to_string(),
-            "###X#".to_string(),
-        ];
-
-        assert_eq!(
-            find_exit_position_from_vec_map(&map),
-            Position { x: 3, y: 4 }
-        );
+    fn test_cell() {
+        let grid = parse_maze_grid("# ###\n#I  #\n# M #\n#   #\n###X").unwrap();
+        assert_eq!(grid.cell(&Position { x: 0, y: 0 }), Cell::Wall);
+        assert_eq!(grid.cell(&Position { x: 1, y: 0 }), Cell::Free);
+        assert_eq!(grid.cell(&Position { x: 1, y: 1 }), Cell::Free);
+        assert_eq!(grid.cell(&Position { x: 2, y: 2 }), Cell::Monster);
+        assert_eq!(grid.cell(&Position { x: 3, y: 4 }), Cell::Free);
+        assert_eq!(grid.cell(&Position { x: 4, y: 4 }), Cell::Wall);
+        assert_eq!(grid.cell(&Position { x: 5, y: 0 }), Cell::Wall);
+        assert_eq!(grid.cell(&Position { x: 0, y: 5 }), Cell::Wall);
     }
 
     #[test]
-    #[should_panic]
-    fn test_find_exit_position_from_vec_map_panic() {
-        let map = vec![
-            "# ###".to_string(),
-            "#   #".to_string(),
-            "# M #".to_string(),
-            "#   #".to_string(),
-            "#####".to_string(),
-        ];
-
-        find_exit_position_from_vec_map(&map);
+    fn test_move_position() {
+        let position = Position { x: 1, y: 1 };
+        assert_eq!(move_position(&position, '^'), Some(Position { x: 1, y: 0 }));
+        assert_eq!(move_position(&position, 'v'), Some(Position { x: 1, y: 2 }));
+        assert_eq!(move_position(&position, '<'), Some(Position { x: 0, y: 1 }));
+        assert_eq!(move_position(&position, '>'), Some(Position { x: 2, y: 1 }));
+        assert_eq!(move_position(&position, 'x'), None);
+        assert_eq!(move_position(&Position { x: 0, y: 0 }, '^'), None);
+        assert_eq!(move_position(&Position { x: 0, y: 0 }, '<'), None);
     }
 
     #[test]
-    fn test_find_player_position_from_vec_map() {
-        let map = vec![
-            "#I###".to_string(),
-            "#   #".to_string(),
-            "# M #".to_string(),
-            "#   #".to_string(),
-            "#####".to_string(),
-        ];
+    fn test_parse_maze_grid() {
+        let expected_grid = MazeGrid {
+            cells: vec![
+                Cell::Wall,
+                Cell::Free,
+                Cell::Wall,
+                Cell::Free,
+                Cell::Monster,
+                Cell::Free,
+                Cell::Wall,
+                Cell::Free,
+                Cell::Wall,
+            ],
+            width: 3,
+            height: 3,
+            start: Position { x: 1, y: 0 },
+            exit: Position { x: 1, y: 2 },
+        };
+        assert_eq!(parse_maze_grid("#I#\n M \n#X#"), Ok(expected_grid.clone()));
+        assert_eq!(parse_maze_grid("│Y│\n M \n└X┘"), Ok(expected_grid));
+    }
 
-        assert_eq!(
-            find_player_position_from_vec_map(&map),
-            Position { x: 1, y: 0 }
-        );
+    #[test]
+    fn test_parse_maze_grid_ragged_lines() {
+        let grid = parse_maze_grid("#I\n     \n#X#").unwrap();
+        assert_eq!(grid.width, 5);
+        assert_eq!(grid.height, 3);
+        assert_eq!(grid.cell(&Position { x: 4, y: 0 }), Cell::Wall);
+        assert_eq!(grid.cell(&Position { x: 4, y: 1 }), Cell::Free);
+        assert_eq!(grid.cell(&Position { x: 3, y: 2 }), Cell::Wall);
+        assert_eq!(solve("#I\n     \n#X#", 1), Ok("vv".to_string()));
     }
 
     #[test]
-    #[should_panic]
-    fn test_find_player_position_from_vec_map_panic() {
-        let map = vec![
-            "# ###".to_string(),
-            "#   #".to_string(),
-            "# M #".to_string(),
-            "#   #".to_string(),
-            "#####".to_string(),
-        ];
-
-        find_player_position_from_vec_map(&map);
+    fn test_parse_maze_grid_counts_chars_not_bytes() {
+        let grid = parse_maze_grid("──I─\n── X").unwrap();
+        assert_eq!(grid.width, 4);
+        assert_eq!(grid.start, Position { x: 2, y: 0 });
+        assert_eq!(grid.exit, Position { x: 3, y: 1 });
     }
 
     #[test]
-    fn test_get_monstrous_maze_map_from_input() {
-        let monstrous_maze_input = MonstrousMazeInput {
-            endurance: 2,
-            grid: "#I###\n#   #\n# M #\n#   #\n###X#".to_string(),
-        };
-        let expected_monstrous_maze_map = MonstrousMazeMap {
-            map: vec![
-                "#I###".to_string(),
-                "#   #".to_string(),
-                "# M #".to_string(),
-                "#   #".to_string(),
-                "###X#".to_string(),
-            ],
-            map_height: 5,
-            map_width: 5,
-            player_position: Position { x: 1, y: 0 },
-            target_position: Position { x: 3, y: 4 },
-        };
+    fn test_parse_maze_grid_missing_start() {
+        assert_eq!(
+            parse_maze_grid("# ###\n#   #\n# M #\n#   #\n###X#"),
+            Err(MazeError::MissingStart)
+        );
+        assert_eq!(solve("#  X", 2), Err(MazeError::MissingStart));
+    }
 
+    #[test]
+    fn test_parse_maze_grid_missing_exit() {
         assert_eq!(
-            get_monstrous_maze_map_from_input(&monstrous_maze_input),
-            expected_monstrous_maze_map
+            parse_maze_grid("#I###\n#   #\n# M #\n#   #\n#####"),
+            Err(MazeError::MissingExit)
         );
     }
 }
@@ -53,6 +53,9 @@ fn main() {
         grid,
         endurance: 2,
     });
-    println!("Output: {}", output.path);
+    match output {
+        Ok(output) => println!("Output: {}", output.path),
+        Err(err) => println!("Error: {}", err),
+    }
     println!("{:?}", i.elapsed());
 }
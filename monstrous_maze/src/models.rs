@@ -14,17 +14,78 @@ pub struct MonstrousMazeOutput {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MonstrousMaze(pub MonstrousMazeInput);
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
-pub struct MonstrousMazeMap {
-    pub map: Vec<String>,
-    pub map_height: usize,
-    pub map_width: usize,
-    pub player_position: Position,
-    pub target_position: Position,
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
 }
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Cell {
+    Free,
+    Wall,
+    Monster,
+}
+
+/// Maze stored row by row in a single vector, `cells[y * width + x]`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MazeGrid {
+    pub cells: Vec<Cell>,
+    pub width: usize,
+    pub height: usize,
+    pub start: Position,
+    pub exit: Position,
+}
+
+impl MazeGrid {
+    pub fn index(&self, position: &Position) -> usize {
+        position.y * self.width + position.x
+    }
+
+    pub fn position(&self, index: usize) -> Position {
+        Position {
+            x: index % self.width,
+            y: index / self.width,
+        }
+    }
+
+    /// Cells outside of the grid are walls
+    pub fn cell(&self, position: &Position) -> Cell {
+        if position.x >= self.width || position.y >= self.height {
+            return Cell::Wall;
+        }
+        self.cells[self.index(position)]
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MazeError {
+    /// The grid has no `I` nor `Y` start
+    MissingStart,
+    /// The grid has no `X` exit
+    MissingExit,
+    /// No path reaches the exit without running out of endurance
+    NoPath,
+    /// The generator settings cannot produce a maze
+    InvalidConfig(String),
+    /// Only `placed` monsters fit without making the maze unsolvable
+    TooManyMonsters { requested: usize, placed: usize },
+}
+
+impl std::fmt::Display for MazeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MazeError::MissingStart => write!(f, "the maze has no start"),
+            MazeError::MissingExit => write!(f, "the maze has no exit"),
+            MazeError::NoPath => write!(f, "no path reaches the exit with this endurance"),
+            MazeError::InvalidConfig(reason) => write!(f, "invalid maze config: {}", reason),
+            MazeError::TooManyMonsters { requested, placed } => write!(
+                f,
+                "only {} of the {} monsters fit in a solvable maze",
+                placed, requested
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MazeError {}
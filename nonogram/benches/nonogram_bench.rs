@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use nonogram::challenge_generator::{generate_nonogram, NonogramConfig};
+use nonogram::challenge_resolve::solve_nonogram;
+use nonogram::models::NonogramInput;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(19);
+    for size in [10, 20] {
+        let config = NonogramConfig {
+            width: size,
+            height: size,
+            ..NonogramConfig::default()
+        };
+        let puzzles: Vec<NonogramInput> = (0..20)
+            .map(|_| generate_nonogram(&config, &mut rng).unwrap())
+            .collect();
+        c.bench_function(
+            &format!("Benchmark nonogram 20 puzzles {}x{}", size, size),
+            |b| {
+                b.iter(|| {
+                    for puzzle in &puzzles {
+                        solve_nonogram(black_box(puzzle)).unwrap();
+                    }
+                })
+            },
+        );
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,204 @@
+use log::debug;
+use rand::Rng;
+
+use crate::challenge_resolve::count_solutions;
+use crate::models::{NonogramError, NonogramInput, NonogramOutput};
+
+#[derive(Debug, Clone)]
+pub struct NonogramConfig {
+    pub width: usize,
+    pub height: usize,
+    /// Probability of a cell to be filled, dense grids are more often uniquely solvable
+    pub density: f64,
+    /// Random grids tried before giving up on finding a uniquely solvable one
+    pub max_attempts: usize,
+}
+
+impl Default for NonogramConfig {
+    fn default() -> Self {
+        NonogramConfig {
+            width: 10,
+            height: 10,
+            density: 0.6,
+            max_attempts: 1000,
+        }
+    }
+}
+
+pub fn generate_nonogram_challenge() -> NonogramInput {
+    generate_default_nonogram(&mut rand::thread_rng())
+}
+
+pub fn generate_default_nonogram<R: Rng>(rng: &mut R) -> NonogramInput {
+    generate_nonogram(&NonogramConfig::default(), rng)
+        .expect("The default nonogram config always gives a puzzle")
+}
+
+/// Draws random grids until the clues of one of them only allow this grid
+pub fn generate_nonogram<R: Rng>(
+    config: &NonogramConfig,
+    rng: &mut R,
+) -> Result<NonogramInput, NonogramError> {
+    if config.width == 0 || config.height == 0 {
+        return Err(NonogramError::InvalidConfig(format!(
+            "a grid of {}x{} cells has no cell",
+            config.width, config.height
+        )));
+    }
+    if !(0.0..=1.0).contains(&config.density) {
+        return Err(NonogramError::InvalidConfig(format!(
+            "density {} is not a probability",
+            config.density
+        )));
+    }
+
+    for attempt in 1..=config.max_attempts {
+        let grid: Vec<Vec<bool>> = (0..config.height)
+            .map(|_| {
+                (0..config.width)
+                    .map(|_| rng.gen_bool(config.density))
+                    .collect()
+            })
+            .collect();
+        let input = clues_of_grid(&grid);
+        if count_solutions(&input, 2) == 1 {
+            debug!("Generated nonogram after {} attempts", attempt);
+            return Ok(input);
+        }
+    }
+    Err(NonogramError::NoUniquePuzzle {
+        attempts: config.max_attempts,
+    })
+}
+
+/// Clues of the rows and columns of a grid given row by row
+pub fn clues_of_grid(grid: &[Vec<bool>]) -> NonogramInput {
+    let width = grid.first().map_or(0, |row| row.len());
+    NonogramInput {
+        rows: grid
+            .iter()
+            .map(|row| line_clue(row.iter().copied()))
+            .collect(),
+        columns: (0..width)
+            .map(|x| line_clue(grid.iter().map(|row| row[x])))
+            .collect(),
+    }
+}
+
+fn line_clue(cells: impl Iterator<Item = bool>) -> Vec<usize> {
+    let mut clue = Vec::new();
+    let mut block = 0;
+    for filled in cells {
+        if filled {
+            block += 1;
+        } else if block > 0 {
+            clue.push(block);
+            block = 0;
+        }
+    }
+    if block > 0 {
+        clue.push(block);
+    }
+    clue
+}
+
+/// The grid must have the size of the clues and the same clues, any such grid is accepted
+pub fn validate_nonogram_challenge(input: &NonogramInput, output: &NonogramOutput) -> bool {
+    output.grid.len() == input.rows.len()
+        && output
+            .grid
+            .iter()
+            .all(|row| row.len() == input.columns.len())
+        && clues_of_grid(&output.grid) == *input
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::challenge_resolve::solve_nonogram;
+
+    use super::*;
+
+    #[test]
+    fn test_clues_of_grid() {
+        let grid = vec![
+            vec![true, true, false, true],
+            vec![false, false, false, false],
+            vec![true, false, true, true],
+        ];
+        let input = clues_of_grid(&grid);
+        assert_eq!(input.rows, vec![vec![2, 1], vec![], vec![1, 2]]);
+        assert_eq!(
+            input.columns,
+            vec![vec![1, 1], vec![1], vec![1], vec![1, 1]]
+        );
+    }
+
+    #[test]
+    fn test_generated_nonograms_are_uniquely_solvable() {
+        let mut rng = StdRng::seed_from_u64(19);
+        for (width, height) in [(1, 1), (5, 5), (10, 10), (15, 8), (20, 20)] {
+            let config = NonogramConfig {
+                width,
+                height,
+                ..NonogramConfig::default()
+            };
+            for _ in 0..5 {
+                let input = generate_nonogram(&config, &mut rng).unwrap();
+                assert_eq!(input.rows.len(), height);
+                assert_eq!(input.columns.len(), width);
+                assert_eq!(count_solutions(&input, 2), 1);
+                let output = solve_nonogram(&input).unwrap();
+                assert!(validate_nonogram_challenge(&input, &output));
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_nonogram_invalid_config() {
+        let mut rng = StdRng::seed_from_u64(19);
+        let config = NonogramConfig {
+            width: 0,
+            ..NonogramConfig::default()
+        };
+        assert!(matches!(
+            generate_nonogram(&config, &mut rng),
+            Err(NonogramError::InvalidConfig(_))
+        ));
+        let config = NonogramConfig {
+            density: 1.5,
+            ..NonogramConfig::default()
+        };
+        assert!(matches!(
+            generate_nonogram(&config, &mut rng),
+            Err(NonogramError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_nonogram_challenge() {
+        let input = NonogramInput {
+            rows: vec![vec![1], vec![1]],
+            columns: vec![vec![1], vec![1]],
+        };
+        let diagonal = |first: bool| NonogramOutput {
+            grid: vec![vec![first, !first], vec![!first, first]],
+        };
+        assert!(validate_nonogram_challenge(&input, &diagonal(true)));
+        assert!(validate_nonogram_challenge(&input, &diagonal(false)));
+        let full = NonogramOutput {
+            grid: vec![vec![true, true], vec![true, true]],
+        };
+        assert!(!validate_nonogram_challenge(&input, &full));
+        let missing_row = NonogramOutput {
+            grid: vec![vec![true, false]],
+        };
+        assert!(!validate_nonogram_challenge(&input, &missing_row));
+        let long_row = NonogramOutput {
+            grid: vec![vec![true, false, false], vec![false, true, false]],
+        };
+        assert!(!validate_nonogram_challenge(&input, &long_row));
+    }
+}
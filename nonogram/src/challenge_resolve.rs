@@ -0,0 +1,340 @@
+use std::collections::VecDeque;
+
+use log::debug;
+
+use crate::models::{Cell, NonogramError, NonogramInput, NonogramOutput};
+
+/// Fills the grid with line solving, guessing a cell and backtracking when the lines alone
+/// cannot decide it
+pub fn solve_nonogram(input: &NonogramInput) -> Result<NonogramOutput, NonogramError> {
+    let mut solutions = find_solutions(input, 1);
+    debug!("Nonogram {:?} solved: {}", input, !solutions.is_empty());
+    solutions
+        .pop()
+        .map(|board| board.to_output())
+        .ok_or(NonogramError::NoSolution)
+}
+
+/// Number of grids satisfying the clues, counting stops at `limit`
+pub fn count_solutions(input: &NonogramInput, limit: usize) -> usize {
+    find_solutions(input, limit).len()
+}
+
+fn find_solutions(input: &NonogramInput, limit: usize) -> Vec<Board> {
+    let board = Board::new(input.columns.len(), input.rows.len());
+    let lines = board.lines().collect();
+    let mut solutions = Vec::new();
+    search(board, input, lines, limit, &mut solutions);
+    solutions
+}
+
+fn search(
+    mut board: Board,
+    input: &NonogramInput,
+    lines: Vec<Line>,
+    limit: usize,
+    solutions: &mut Vec<Board>,
+) {
+    if !board.propagate(input, lines) {
+        return;
+    }
+    let index = match board.cells.iter().position(|cell| *cell == Cell::Unknown) {
+        Some(index) => index,
+        None => {
+            solutions.push(board);
+            return;
+        }
+    };
+    for guess in [Cell::Filled, Cell::Empty] {
+        if solutions.len() >= limit {
+            return;
+        }
+        let mut next = board.clone();
+        next.cells[index] = guess;
+        let lines = vec![
+            Line::Row(index / board.width),
+            Line::Column(index % board.width),
+        ];
+        search(next, input, lines, limit, solutions);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+/// Grid being solved, stored row by row in a single vector, `cells[y * width + x]`
+#[derive(Debug, Clone)]
+struct Board {
+    cells: Vec<Cell>,
+    width: usize,
+    height: usize,
+}
+
+impl Board {
+    fn new(width: usize, height: usize) -> Board {
+        Board {
+            cells: vec![Cell::Unknown; width * height],
+            width,
+            height,
+        }
+    }
+
+    fn lines(&self) -> impl Iterator<Item = Line> {
+        (0..self.height)
+            .map(Line::Row)
+            .chain((0..self.width).map(Line::Column))
+    }
+
+    /// Grid indexes of the cells of the line, in clue order
+    fn indexes(&self, line: Line) -> Vec<usize> {
+        match line {
+            Line::Row(y) => (0..self.width).map(|x| y * self.width + x).collect(),
+            Line::Column(x) => (0..self.height).map(|y| y * self.width + x).collect(),
+        }
+    }
+
+    fn clue<'a>(&self, input: &'a NonogramInput, line: Line) -> &'a [usize] {
+        match line {
+            Line::Row(y) => &input.rows[y],
+            Line::Column(x) => &input.columns[x],
+        }
+    }
+
+    /// Solves the lines until none of them changes, the lines crossing a new cell are solved
+    /// again. Returns `false` when a line has no placement left.
+    fn propagate(&mut self, input: &NonogramInput, lines: Vec<Line>) -> bool {
+        let mut queued = vec![false; self.height + self.width];
+        let queue_index = |line: Line| match line {
+            Line::Row(y) => y,
+            Line::Column(x) => self.height + x,
+        };
+        for line in &lines {
+            queued[queue_index(*line)] = true;
+        }
+        let mut queue = VecDeque::from(lines);
+
+        while let Some(line) = queue.pop_front() {
+            queued[queue_index(line)] = false;
+            let indexes = self.indexes(line);
+            let cells: Vec<Cell> = indexes.iter().map(|index| self.cells[*index]).collect();
+            let solved = match solve_line(&cells, self.clue(input, line)) {
+                Some(solved) => solved,
+                None => return false,
+            };
+            for (i, index) in indexes.into_iter().enumerate() {
+                if cells[i] == solved[i] {
+                    continue;
+                }
+                self.cells[index] = solved[i];
+                let crossing = match line {
+                    Line::Row(_) => Line::Column(index % self.width),
+                    Line::Column(_) => Line::Row(index / self.width),
+                };
+                if !queued[queue_index(crossing)] {
+                    queued[queue_index(crossing)] = true;
+                    queue.push_back(crossing);
+                }
+            }
+        }
+        true
+    }
+
+    fn to_output(&self) -> NonogramOutput {
+        NonogramOutput {
+            grid: (0..self.height)
+                .map(|y| {
+                    self.cells[y * self.width..(y + 1) * self.width]
+                        .iter()
+                        .map(|cell| *cell == Cell::Filled)
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Known cells of the line once every placement of the clue blocks agreeing with the current
+/// cells is considered: a cell is known when all of them fill it or all of them leave it empty.
+/// Returns `None` when no placement fits.
+pub fn solve_line(cells: &[Cell], clue: &[usize]) -> Option<Vec<Cell>> {
+    let (n, k) = (cells.len(), clue.len());
+    // empty_count[i]: empty cells before i, to check a block in constant time
+    let mut empty_count = vec![0; n + 1];
+    for (i, cell) in cells.iter().enumerate() {
+        empty_count[i + 1] = empty_count[i] + (*cell == Cell::Empty) as usize;
+    }
+    // Block `j` starting at `i`: its end, and where the next block may start after the gap
+    let place = |i: usize, j: usize| -> Option<(usize, usize)> {
+        let end = i + clue[j];
+        if end > n || empty_count[end] != empty_count[i] {
+            return None;
+        }
+        match cells.get(end) {
+            None => Some((end, n)),
+            Some(Cell::Filled) => None,
+            Some(_) => Some((end, end + 1)),
+        }
+    };
+
+    // fits[i][j]: blocks `j..` can be placed in cells `i..`
+    let mut fits = vec![vec![false; k + 1]; n + 1];
+    fits[n][k] = true;
+    for i in (0..n).rev() {
+        for j in 0..=k {
+            fits[i][j] = (cells[i] != Cell::Filled && fits[i + 1][j])
+                || (j < k && place(i, j).is_some_and(|(_, next)| fits[next][j + 1]));
+        }
+    }
+    if !fits[0][0] {
+        return None;
+    }
+
+    let mut reached = vec![vec![false; k + 1]; n + 1];
+    reached[0][0] = true;
+    let mut can_fill = vec![false; n];
+    let mut can_empty = vec![false; n];
+    for i in 0..n {
+        for j in 0..=k {
+            if !reached[i][j] {
+                continue;
+            }
+            if cells[i] != Cell::Filled && fits[i + 1][j] {
+                can_empty[i] = true;
+                reached[i + 1][j] = true;
+            }
+            if j == k {
+                continue;
+            }
+            if let Some((end, next)) = place(i, j).filter(|(_, next)| fits[*next][j + 1]) {
+                can_fill[i..end].iter_mut().for_each(|fill| *fill = true);
+                if end < n {
+                    can_empty[end] = true;
+                }
+                reached[next][j + 1] = true;
+            }
+        }
+    }
+    Some(
+        (0..n)
+            .map(|i| match (can_fill[i], can_empty[i]) {
+                (true, false) => Cell::Filled,
+                (false, true) => Cell::Empty,
+                _ => Cell::Unknown,
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge_generator::validate_nonogram_challenge;
+
+    fn parse_line(line: &str) -> Vec<Cell> {
+        line.chars()
+            .map(|c| match c {
+                '#' => Cell::Filled,
+                '.' => Cell::Empty,
+                _ => Cell::Unknown,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_solve_line_overlap() {
+        assert_eq!(
+            solve_line(&parse_line("?????"), &[4]),
+            Some(parse_line("?###?"))
+        );
+        assert_eq!(
+            solve_line(&parse_line("?????"), &[2, 2]),
+            Some(parse_line("##.##"))
+        );
+        assert_eq!(
+            solve_line(&parse_line("?????"), &[]),
+            Some(parse_line("....."))
+        );
+        assert_eq!(
+            solve_line(&parse_line("?????"), &[1]),
+            Some(parse_line("?????"))
+        );
+    }
+
+    #[test]
+    fn test_solve_line_known_cells() {
+        assert_eq!(
+            solve_line(&parse_line("?#???"), &[1]),
+            Some(parse_line(".#..."))
+        );
+        assert_eq!(
+            solve_line(&parse_line("??.??"), &[2]),
+            Some(parse_line("??.??"))
+        );
+        assert_eq!(
+            solve_line(&parse_line("?.???"), &[2]),
+            Some(parse_line("..?#?"))
+        );
+        assert_eq!(
+            solve_line(&parse_line("###.?"), &[3, 1]),
+            Some(parse_line("###.#"))
+        );
+    }
+
+    #[test]
+    fn test_solve_line_contradiction() {
+        assert_eq!(solve_line(&parse_line("????"), &[2, 2]), None);
+        assert_eq!(
+            solve_line(&parse_line("?#?#?"), &[3]),
+            Some(parse_line(".###."))
+        );
+        assert_eq!(solve_line(&parse_line("#.#"), &[3]), None);
+        assert_eq!(solve_line(&parse_line("..#"), &[]), None);
+    }
+
+    #[test]
+    fn test_solve_nonogram() {
+        // .#.
+        // ###
+        // .#.
+        let input = NonogramInput {
+            rows: vec![vec![1], vec![3], vec![1]],
+            columns: vec![vec![1], vec![3], vec![1]],
+        };
+        let output = solve_nonogram(&input).unwrap();
+        assert_eq!(
+            output.grid,
+            vec![
+                vec![false, true, false],
+                vec![true, true, true],
+                vec![false, true, false]
+            ]
+        );
+        assert!(validate_nonogram_challenge(&input, &output));
+    }
+
+    #[test]
+    fn test_solve_nonogram_with_guess() {
+        // Two diagonals satisfy the clues, line solving alone cannot choose
+        let input = NonogramInput {
+            rows: vec![vec![1], vec![1]],
+            columns: vec![vec![1], vec![1]],
+        };
+        let output = solve_nonogram(&input).unwrap();
+        assert!(validate_nonogram_challenge(&input, &output));
+        assert_eq!(count_solutions(&input, 10), 2);
+        assert_eq!(count_solutions(&input, 1), 1);
+    }
+
+    #[test]
+    fn test_solve_nonogram_without_solution() {
+        let input = NonogramInput {
+            rows: vec![vec![2], vec![]],
+            columns: vec![vec![1], vec![]],
+        };
+        assert_eq!(solve_nonogram(&input), Err(NonogramError::NoSolution));
+        assert_eq!(count_solutions(&input, 2), 0);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod challenge_generator;
+pub mod challenge_resolve;
+pub mod models;
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// Lengths of the filled blocks of every line, from left to right for the rows and from top to
+/// bottom for the columns. An empty line has no block.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NonogramInput {
+    pub rows: Vec<Vec<usize>>,
+    pub columns: Vec<Vec<usize>>,
+}
+
+/// Grid row by row, `true` for a filled cell
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NonogramOutput {
+    pub grid: Vec<Vec<bool>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Nonogram(pub NonogramInput);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Cell {
+    Unknown,
+    Filled,
+    Empty,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NonogramError {
+    /// No grid satisfies every clue
+    NoSolution,
+    /// The generator settings cannot produce a puzzle
+    InvalidConfig(String),
+    /// Every random grid tried had several solutions
+    NoUniquePuzzle { attempts: usize },
+}
+
+impl std::fmt::Display for NonogramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NonogramError::NoSolution => write!(f, "no grid satisfies the clues"),
+            NonogramError::InvalidConfig(reason) => {
+                write!(f, "invalid nonogram config: {}", reason)
+            }
+            NonogramError::NoUniquePuzzle { attempts } => write!(
+                f,
+                "no uniquely solvable puzzle found in {} attempts",
+                attempts
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NonogramError {}
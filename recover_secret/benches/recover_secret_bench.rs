@@ -5,7 +5,7 @@ use recover_secret::challenge_resolve::solve_secret_string_challenge;
 
 fn criterion_benchmark_1(c: &mut Criterion) {
     c.bench_function("Benchmark recover secret random String", |b| {
-        b.iter(|| solve_secret_string_challenge(black_box(&generate_challenge())))
+        b.iter(|| solve_secret_string_challenge(black_box(&generate_challenge().input)).unwrap())
     });
 }
 
@@ -1,28 +1,202 @@
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use rand::seq::SliceRandom;
 use rand::Rng;
 
-use utils::string_utils::{generate_random_tuple, is_sequence_valid, word_count};
+use utils::file_utils::read_word_list_file_macro;
+use utils::string_utils::{is_sequence_valid, word_count};
 
 use crate::challenge_resolve::retrieve_tuples_from_letters;
-use crate::models::{RecoverSecretInput, RecoverSecretOutput};
+use crate::models::{RecoverSecretChallenge, RecoverSecretInput, RecoverSecretOutput};
+
+/// Complexity used when none is requested, a short sentence of dictionary words
+pub const DEFAULT_COMPLEXITY: u8 = 18;
+
+/// Hidden sentence of the complexity 0
+pub const FIRST_SENTENCE: &str = "C'est chou";
+
+/// Hidden sentence of the complexity 17, the first one with repeated letters
+pub const REPEATED_LETTERS_SENTENCE: &str = "Il fait froid";
+
+const RANDOM_LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SentenceSource {
+    /// Always the same sentence
+    Fixed(String),
+    /// Words of random letters, all different while the alphabet allows it
+    RandomLetters { word_length: RangeInclusive<usize> },
+    /// Words of `data/liste-mots-alphabetique.txt`
+    Dictionary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretConfig {
+    pub word_count: RangeInclusive<usize>,
+    pub source: SentenceSource,
+    /// Letters per tuple, capped by the sentence length
+    pub tuple_size: RangeInclusive<usize>,
+}
+
+impl SecretConfig {
+    /// Levels of the `--complexity` server option, described in `recover_secret.md`
+    ///
+    /// | complexity  | hidden sentence                                                     |
+    /// |-------------|---------------------------------------------------------------------|
+    /// | 0           | `C'est chou`                                                        |
+    /// | 1 - 16      | `1 + (complexity - 1) / 4` words of 3 to `3 + complexity / 2`      |
+    /// |             | random letters, all different                                       |
+    /// | 17          | `Il fait froid`, letters are repeated                               |
+    /// | 18          | 2 or 3 dictionary words                                             |
+    /// | 19          | 3 or 4 dictionary words                                             |
+    /// | 20 and more | 4 to 6 dictionary words                                             |
+    pub fn from_complexity(complexity: u8) -> SecretConfig {
+        let fixed = |sentence: &str| {
+            let sentence = sentence.to_string();
+            let words = word_count(&sentence);
+            (words..=words, SentenceSource::Fixed(sentence))
+        };
+        let (word_count, source) = match complexity {
+            0 => fixed(FIRST_SENTENCE),
+            1..=16 => {
+                let words = 1 + (complexity as usize - 1) / 4;
+                let word_length = 3..=3 + complexity as usize / 2;
+                (words..=words, SentenceSource::RandomLetters { word_length })
+            }
+            17 => fixed(REPEATED_LETTERS_SENTENCE),
+            18 => (2..=3, SentenceSource::Dictionary),
+            19 => (3..=4, SentenceSource::Dictionary),
+            _ => (4..=6, SentenceSource::Dictionary),
+        };
+        SecretConfig {
+            word_count,
+            source,
+            tuple_size: 3..=6,
+        }
+    }
+}
+
+impl Default for SecretConfig {
+    fn default() -> Self {
+        SecretConfig::from_complexity(DEFAULT_COMPLEXITY)
+    }
+}
 
-pub fn generate_challenge() -> RecoverSecretInput {
-    let mut rng = rand::thread_rng();
-    let word_count = rng.gen_range(1..=5);
-    let mut tuple_sizes: Vec<usize> = Vec::new();
-    let mut letters: String = String::new();
+pub fn generate_challenge() -> RecoverSecretChallenge {
+    generate_complexity_challenge(DEFAULT_COMPLEXITY)
+}
 
-    for _ in 0..rng.gen_range(5..10) {
-        let tuple_size = rng.gen_range(1..=8);
-        tuple_sizes.push(tuple_size);
-        let tuple = generate_random_tuple(&tuple_size);
-        letters.push_str(tuple.iter().collect::<String>().as_str());
+/// Complexity of a difficulty between 0 and 1, among the random letters levels 1 to 16 whose
+/// sentences grow with the complexity
+pub fn complexity_of_difficulty(difficulty: f64) -> u8 {
+    1 + (difficulty.clamp(0.0, 1.0) * 15.0).round() as u8
+}
+
+pub fn generate_complexity_challenge(complexity: u8) -> RecoverSecretChallenge {
+    generate_secret_challenge(
+        &SecretConfig::from_complexity(complexity),
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Samples a hidden sentence then derives the tuples sent to the players from it
+pub fn generate_secret_challenge<R: Rng>(
+    config: &SecretConfig,
+    rng: &mut R,
+) -> RecoverSecretChallenge {
+    let secret_sentence = generate_sentence(config, rng);
+    let tuples = derive_tuples(&secret_sentence, config.tuple_size.clone(), rng);
+    RecoverSecretChallenge {
+        input: RecoverSecretInput {
+            word_count: word_count(&secret_sentence),
+            letters: tuples.iter().flatten().collect(),
+            tuple_sizes: tuples.iter().map(|tuple| tuple.len()).collect(),
+        },
+        secret_sentence,
     }
+}
+
+pub fn generate_sentence<R: Rng>(config: &SecretConfig, rng: &mut R) -> String {
+    let word_count = rng.gen_range(config.word_count.clone()).max(1);
+    let words: Vec<String> = match &config.source {
+        SentenceSource::Fixed(sentence) => return sentence.clone(),
+        SentenceSource::RandomLetters { word_length } => {
+            let mut alphabet: Vec<char> = RANDOM_LETTERS.chars().collect();
+            alphabet.shuffle(rng);
+            let mut letters = alphabet.into_iter().cycle();
+            (0..word_count)
+                .map(|_| {
+                    let length = rng.gen_range(word_length.clone()).max(1);
+                    letters.by_ref().take(length).collect()
+                })
+                .collect()
+        }
+        SentenceSource::Dictionary => {
+            let dictionary = dictionary_words();
+            (0..word_count)
+                .map(|_| {
+                    dictionary
+                        .choose(rng)
+                        .expect("The word list is empty")
+                        .clone()
+                })
+                .collect()
+        }
+    };
+    words.join(" ")
+}
+
+/// Words of the embedded word list, the first column of every line
+pub fn dictionary_words() -> Vec<String> {
+    let mut known_words = HashSet::new();
+    read_word_list_file_macro()
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|word| known_words.insert(*word))
+        .map(|word| word.to_string())
+        .collect()
+}
+
+/// Picks ordered letters of the sentence until every two neighbouring letters, spaces
+/// included, appear next to each other in a tuple: the tuples then fix the whole order of
+/// the sentence and so its word count.
+pub fn derive_tuples<R: Rng>(
+    sentence: &str,
+    tuple_size: RangeInclusive<usize>,
+    rng: &mut R,
+) -> Vec<Vec<char>> {
+    let letters: Vec<char> = sentence.chars().collect();
+    if letters.len() < 2 {
+        return vec![letters];
+    }
+
+    // uncovered[i] while letters i and i + 1 never followed each other in a tuple
+    let mut uncovered = vec![true; letters.len() - 1];
+    let mut tuples = Vec::new();
+    loop {
+        let uncovered_pairs: Vec<usize> = (0..uncovered.len()).filter(|i| uncovered[*i]).collect();
+        let pair = match uncovered_pairs.choose(rng) {
+            Some(pair) => *pair,
+            None => break,
+        };
+        let size = rng.gen_range(tuple_size.clone()).clamp(2, letters.len());
+        let others: Vec<usize> = (0..letters.len())
+            .filter(|index| *index != pair && *index != pair + 1)
+            .collect();
+        let mut indexes: Vec<usize> = others.choose_multiple(rng, size - 2).copied().collect();
+        indexes.extend([pair, pair + 1]);
+        indexes.sort_unstable();
 
-    RecoverSecretInput {
-        word_count,
-        letters,
-        tuple_sizes,
+        for window in indexes.windows(2) {
+            if window[1] == window[0] + 1 {
+                uncovered[window[0]] = false;
+            }
+        }
+        tuples.push(indexes.iter().map(|index| letters[*index]).collect());
     }
+    tuples.shuffle(rng);
+    tuples
 }
 
 pub fn validate_challenge(
@@ -33,27 +207,113 @@ pub fn validate_challenge(
         return false;
     }
 
-    for tuple in retrieve_tuples_from_letters(&challenge_input) {
-        if !is_sequence_valid(&challenge_output.secret_sentence, &tuple) {
-            return false;
-        }
-    }
-    return true;
+    retrieve_tuples_from_letters(challenge_input)
+        .iter()
+        .all(|tuple| is_sequence_valid(&challenge_output.secret_sentence, tuple))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::challenge_generator::{generate_challenge, validate_challenge};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::challenge_generator::*;
     use crate::models::{RecoverSecretInput, RecoverSecretOutput};
 
     #[test]
     fn test_generate_challenge() {
-        let challenge_input = generate_challenge();
+        let challenge = generate_challenge();
+        let challenge_input = challenge.input;
         assert_eq!(
-            challenge_input.letters.len(),
+            challenge_input.letters.chars().count(),
             challenge_input.tuple_sizes.iter().sum()
         );
-        assert!(challenge_input.word_count <= challenge_input.letters.len());
+        assert_eq!(
+            challenge_input.word_count,
+            challenge.secret_sentence.split(' ').count()
+        );
+    }
+
+    #[test]
+    fn test_generated_challenge_accepts_its_secret() {
+        let mut rng = StdRng::seed_from_u64(13);
+        for complexity in 0..=21 {
+            let config = SecretConfig::from_complexity(complexity);
+            for _ in 0..20 {
+                let challenge = generate_secret_challenge(&config, &mut rng);
+                assert!(config.word_count.contains(&challenge.input.word_count));
+                assert!(validate_challenge(
+                    &challenge.input,
+                    &RecoverSecretOutput {
+                        secret_sentence: challenge.secret_sentence.clone()
+                    }
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_letters_are_all_different() {
+        let config = SecretConfig::from_complexity(5);
+        let sentence = generate_sentence(&config, &mut StdRng::seed_from_u64(1));
+        let letters: Vec<char> = sentence.chars().filter(|letter| *letter != ' ').collect();
+        let distinct: HashSet<char> = letters.iter().copied().collect();
+        assert_eq!(letters.len(), distinct.len());
+        assert_eq!(sentence.split(' ').count(), 2);
+    }
+
+    #[test]
+    fn test_random_letters_of_every_level_are_all_different() {
+        let mut rng = StdRng::seed_from_u64(16);
+        for complexity in 1..=16 {
+            let config = SecretConfig::from_complexity(complexity);
+            let sentence = generate_sentence(&config, &mut rng);
+            let letters: Vec<char> = sentence.chars().filter(|letter| *letter != ' ').collect();
+            let distinct: HashSet<char> = letters.iter().copied().collect();
+            assert_eq!(letters.len(), distinct.len());
+        }
+    }
+
+    #[test]
+    fn test_fixed_sentences() {
+        let mut rng = StdRng::seed_from_u64(16);
+        let sentence = generate_sentence(&SecretConfig::from_complexity(0), &mut rng);
+        assert_eq!(sentence, FIRST_SENTENCE);
+        let challenge = generate_secret_challenge(&SecretConfig::from_complexity(17), &mut rng);
+        assert_eq!(challenge.secret_sentence, REPEATED_LETTERS_SENTENCE);
+        assert_eq!(challenge.input.word_count, 3);
+    }
+
+    #[test]
+    fn test_dictionary_words() {
+        let words = dictionary_words();
+        assert!(words.contains(&"abandonner".to_string()));
+        assert!(words.contains(&"âme".to_string()));
+        assert!(words.contains(&"aujourd'hui".to_string()));
+        let distinct: HashSet<&String> = words.iter().collect();
+        assert_eq!(words.len(), distinct.len());
+    }
+
+    #[test]
+    fn test_derive_tuples_cover_every_neighbouring_letters() {
+        let sentence = "il fait froid";
+        let letters: Vec<char> = sentence.chars().collect();
+        let tuples = derive_tuples(sentence, 3..=4, &mut StdRng::seed_from_u64(5));
+        for pair in letters.windows(2) {
+            assert!(tuples
+                .iter()
+                .any(|tuple| tuple.windows(2).any(|window| window == pair)));
+        }
+        for tuple in &tuples {
+            assert!(is_sequence_valid(sentence, tuple));
+            assert!((2..=4).contains(&tuple.len()));
+        }
+    }
+
+    #[test]
+    fn test_derive_tuples_of_a_single_letter() {
+        let tuples = derive_tuples("a", 3..=6, &mut StdRng::seed_from_u64(0));
+        assert_eq!(tuples, vec![vec!['a']]);
     }
 
     #[test]
@@ -127,4 +387,15 @@ mod tests {
             &recover_secret_output
         ));
     }
+
+    #[test]
+    fn test_complexity_of_difficulty() {
+        assert_eq!(complexity_of_difficulty(0.0), 1);
+        assert_eq!(complexity_of_difficulty(1.0), 16);
+        assert_eq!(complexity_of_difficulty(-1.0), 1);
+        let complexities: Vec<u8> = (0..=10)
+            .map(|step| complexity_of_difficulty(step as f64 / 10.0))
+            .collect();
+        assert!(complexities.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
 }
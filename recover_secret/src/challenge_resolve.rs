@@ -1,592 +1,486 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 
-use utils::string_utils::{
-    add_char_at_index, add_spaces_in_sequence, get_string_after_last_occurrence,
-    get_string_after_vec_sequence, get_string_before_last_occurrence,
-    get_string_before_vec_sequence_inclusive, is_present, word_count,
-};
-
-use crate::models::{RecoverSecretInput, RecoverSecretOutput};
+use crate::dictionary::Trie;
+use crate::models::{RecoverSecretError, RecoverSecretInput, RecoverSecretOutput};
 
+/// Searches the first sentence of dictionary words satisfying every tuple, see [`SentenceSearch`]
 pub fn solve_secret_sentence_challenge(
     input: &RecoverSecretInput,
-    dictionary: &HashSet<String>,
-) -> RecoverSecretOutput {
-    // println!("Solving challenge...\n{:?}", input);
-    let mut tuples = retrieve_tuples_from_letters(&input);
-    let secret_sentence =
-        retrieve_secret_sentence_from_tuples(&mut tuples, &input.word_count, dictionary, &true);
-    RecoverSecretOutput { secret_sentence }
+    dictionary: &Trie,
+) -> Result<RecoverSecretOutput, RecoverSecretError> {
+    check_tuple_sizes(input)?;
+    let tuples = retrieve_tuples_from_letters(input);
+    let secret_sentence = SentenceSearch::new(&tuples, dictionary, input.word_count)
+        .run()
+        .ok_or(RecoverSecretError::NoSentenceFound {
+            word_count: input.word_count,
+        })?;
+    Ok(RecoverSecretOutput { secret_sentence })
 }
 
-pub fn solve_secret_sentence_challenge_cheat() -> RecoverSecretOutput {
-    // println!("Solving challenge...\n{:?}", input);
+/// Orders the letters of the tuples then splits them in `word_count` words
+pub fn solve_secret_string_challenge(
+    input: &RecoverSecretInput,
+) -> Result<RecoverSecretOutput, RecoverSecretError> {
+    check_tuple_sizes(input)?;
+    // Longest tuples first, they share the most occurrences with the others. Another order
+    // merges the occurrences differently, it is tried when the first graph splits too many words.
+    let mut tuples = retrieve_tuples_from_letters(input);
+    tuples.sort_by_key(|tuple| Reverse(tuple.len()));
+    let graph = ConstraintGraph::from_tuples(&tuples);
+    let secret_sentence = match insert_spaces(&graph.topological_order(), input.word_count) {
+        Err(RecoverSecretError::TooManyWords { .. }) => {
+            tuples.reverse();
+            let graph = ConstraintGraph::from_tuples(&tuples);
+            insert_spaces(&graph.topological_order(), input.word_count)?
+        }
+        result => result?,
+    };
+    Ok(RecoverSecretOutput { secret_sentence })
+}
 
-    RecoverSecretOutput {
-        secret_sentence: "Il fait froid".to_string(),
+/// Letters expected by no tuple a sentence may use, see [`SentenceSearch`]
+const MAX_EXTRA_LETTERS: usize = 16;
+/// Dead ends found by [`SentenceSearch`] before it gives up
+const MAX_DEAD_ENDS: usize = 1 << 18;
+
+fn check_tuple_sizes(input: &RecoverSecretInput) -> Result<(), RecoverSecretError> {
+    let letter_count = input.letters.chars().count();
+    let tuple_sizes = input.tuple_sizes.iter().sum();
+    if letter_count != tuple_sizes {
+        return Err(RecoverSecretError::InvalidTupleSizes {
+            letters: letter_count,
+            tuple_sizes,
+        });
     }
+    Ok(())
 }
 
-pub fn solve_secret_string_challenge(input: &RecoverSecretInput) -> RecoverSecretOutput {
-    // println!("Solving challenge...\n{:?}", input);
-    let mut tuples = retrieve_tuples_from_letters(&input);
-    let secret_sentence = retrieve_secret_sentence_from_tuples(
-        &mut tuples,
-        &input.word_count,
-        &HashSet::new(),
-        &false,
-    );
-    RecoverSecretOutput { secret_sentence }
+/// Precedence graph between letter occurrences: a letter repeated in the secret is one node
+/// per occurrence and every tuple is a chain of edges between the occurrences it uses
+#[derive(Debug, Default)]
+pub struct ConstraintGraph {
+    letters: Vec<char>,
+    successors: Vec<Vec<usize>>,
+    /// A topological order of the occurrences, kept up to date while tuples are added
+    order: Vec<usize>,
 }
 
-pub fn solve_secret_string_challenge_cheat(input: &RecoverSecretInput) -> RecoverSecretOutput {
-    // println!("Solving challenge...\n{:?}", input);
-    let secret_sentence = find_sequence(&vec![input.letters.to_string()], &input.word_count);
-    RecoverSecretOutput { secret_sentence }
-}
+impl ConstraintGraph {
+    pub fn from_tuples(tuples: &[Vec<char>]) -> ConstraintGraph {
+        let mut graph = ConstraintGraph::default();
+        for tuple in tuples {
+            graph.add_tuple(tuple);
+        }
+        graph
+    }
 
-pub fn retrieve_tuples_from_letters(input: &RecoverSecretInput) -> Vec<Vec<char>> {
-    let mut tuples: Vec<Vec<char>> = Vec::new();
-    let mut current_index = 0;
-    input.tuple_sizes.iter().for_each(|size| {
-        let mut tuple: Vec<char> = Vec::new();
-        for i in current_index..(current_index + *size) {
-            tuple.push(
-                input
-                    .letters
-                    .chars()
-                    .nth(i)
-                    .expect("Unable to get char for tuple"),
-            );
+    /// Matches the tuple against the current order with a longest common subsequence, matched
+    /// letters reuse their occurrence. Any other letter reuses an occurrence the graph still
+    /// allows between its neighbours in the tuple, or becomes a new occurrence.
+    fn add_tuple(&mut self, tuple: &[char]) {
+        let matches = self.align(tuple);
+        let mut occurrences: Vec<usize> = Vec::with_capacity(tuple.len());
+        for (i, letter) in tuple.iter().enumerate() {
+            let previous = occurrences.last().copied();
+            let next_match = matches[i + 1..].iter().flatten().next().copied();
+            let fits = |occurrence: usize| {
+                !occurrences.contains(&occurrence)
+                    && previous.is_none_or(|previous| !self.reaches(occurrence, previous))
+                    && next_match.is_none_or(|next| !self.reaches(next, occurrence))
+            };
+            let occurrence = matches[i]
+                .filter(|occurrence| fits(*occurrence))
+                .or_else(|| {
+                    (0..self.letters.len()).find(|occurrence| {
+                        self.letters[*occurrence] == *letter && fits(*occurrence)
+                    })
+                })
+                .unwrap_or_else(|| {
+                    self.letters.push(*letter);
+                    self.successors.push(Vec::new());
+                    self.letters.len() - 1
+                });
+            if let Some(previous) = previous {
+                if !self.successors[previous].contains(&occurrence) {
+                    self.successors[previous].push(occurrence);
+                }
+            }
+            occurrences.push(occurrence);
         }
-        tuples.push(tuple);
-        current_index += *size;
-    });
-    tuples
-}
+        self.order = self.topological_occurrences();
+    }
 
-fn retrieve_secret_sentence_from_tuples(
-    tuples: &mut Vec<Vec<char>>,
-    nb_words: &usize,
-    dictionary: &HashSet<String>,
-    is_sentence_valid: &bool,
-) -> String {
-    let propositions =
-        retrieve_possible_strings_from_tuples(tuples, &mut Vec::new(), nb_words, is_sentence_valid);
+    /// Occurrence of the current order matched by every letter of the tuple, if any
+    fn align(&self, tuple: &[char]) -> Vec<Option<usize>> {
+        let order_letters: Vec<char> = self
+            .order
+            .iter()
+            .map(|index| self.letters[*index])
+            .collect();
+        let (tuple_len, order_len) = (tuple.len(), order_letters.len());
+        // scores[i][j] is the best alignment of tuple[i..] with order[j..]
+        let mut scores = vec![vec![0; order_len + 1]; tuple_len + 1];
+        for i in (0..tuple_len).rev() {
+            for j in (0..order_len).rev() {
+                let skip = scores[i + 1][j].max(scores[i][j + 1]);
+                scores[i][j] = if tuple[i] == order_letters[j] {
+                    skip.max(match_score(tuple[i]) + scores[i + 1][j + 1])
+                } else {
+                    skip
+                };
+            }
+        }
 
-    // display_possibilities(&propositions);
+        let mut matches = vec![None; tuple_len];
+        let (mut i, mut j) = (0, 0);
+        while i < tuple_len && j < order_len {
+            if tuple[i] == order_letters[j]
+                && scores[i][j] == match_score(tuple[i]) + scores[i + 1][j + 1]
+            {
+                matches[i] = Some(self.order[j]);
+                i += 1;
+                j += 1;
+            } else if scores[i][j] == scores[i][j + 1] {
+                j += 1;
+            } else {
+                i += 1;
+            }
+        }
+        matches
+    }
 
-    if propositions.len() > 0 {
-        if *is_sentence_valid {
-            return find_sentence(&propositions, dictionary);
+    /// Whether `to` must come after `from`, an occurrence reaches itself
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut visited = vec![false; self.letters.len()];
+        let mut stack = vec![from];
+        while let Some(occurrence) = stack.pop() {
+            if occurrence == to {
+                return true;
+            }
+            if !visited[occurrence] {
+                visited[occurrence] = true;
+                stack.extend(&self.successors[occurrence]);
+            }
         }
-        return find_sequence(&propositions, nb_words);
-    } else {
-        panic!("No solution found.");
+        false
     }
-}
 
-fn find_sequence(propositions: &Vec<String>, nb_words: &usize) -> String {
-    let sequence = propositions[0].clone();
-    let current_word_count = word_count(&sequence);
-    if current_word_count == *nb_words {
-        return sequence;
+    pub fn topological_order(&self) -> Vec<char> {
+        self.order
+            .iter()
+            .map(|occurrence| self.letters[*occurrence])
+            .collect()
     }
 
-    return add_spaces_in_sequence(&sequence, &(*nb_words - current_word_count));
-}
+    /// Kahn's algorithm. Letters follow letters and spaces follow spaces whenever possible,
+    /// so the order splits as few words as the tuples allow.
+    fn topological_occurrences(&self) -> Vec<usize> {
+        let mut predecessor_counts = vec![0; self.letters.len()];
+        self.successors
+            .iter()
+            .flatten()
+            .for_each(|occurrence| predecessor_counts[*occurrence] += 1);
+        // Ready occurrences, letters then spaces, the first created is taken first
+        let mut ready = [BinaryHeap::new(), BinaryHeap::new()];
+        for occurrence in 0..self.letters.len() {
+            if predecessor_counts[occurrence] == 0 {
+                ready[self.is_space(occurrence)].push(Reverse(occurrence));
+            }
+        }
 
-/*fn display_possibilities(propositions: &Vec<String>) {
-    if propositions.len() > 0 {
-        println!("===================================================");
-        for i in 0..propositions.len() {
-            println!("{:?}", propositions[i])
+        let mut order = Vec::with_capacity(self.letters.len());
+        let mut last_is_space = false;
+        loop {
+            let kind = if ready[last_is_space as usize].is_empty() {
+                !last_is_space as usize
+            } else {
+                last_is_space as usize
+            };
+            let occurrence = match ready[kind].pop() {
+                Some(Reverse(occurrence)) => occurrence,
+                None => break,
+            };
+            order.push(occurrence);
+            last_is_space = kind == 1;
+            for next in &self.successors[occurrence] {
+                predecessor_counts[*next] -= 1;
+                if predecessor_counts[*next] == 0 {
+                    ready[self.is_space(*next)].push(Reverse(*next));
+                }
+            }
         }
-        println!("===================================================");
-        println!("{} possibilities ...", propositions.len(),);
-    } else {
-        println!("No solution found.");
+        order
     }
-}*/
-
-fn retrieve_possible_strings_from_tuples(
-    tuples: &mut Vec<Vec<char>>,
-    propositions: &mut Vec<String>,
-    nb_words: &usize,
-    is_sentence_valid: &bool,
-) -> Vec<String> {
-    if tuples.is_empty() {
-        // println!("{} propositions found.", propositions.len());
-        return propositions.clone();
-    } else if propositions.len() > 0 {
-        // println!("{} propositions found.", propositions.len());
+
+    fn is_space(&self, occurrence: usize) -> usize {
+        (self.letters[occurrence] == ' ') as usize
     }
+}
 
-    let tuple = tuples.remove(0);
-    return if propositions.is_empty() {
-        let string = tuple.iter().collect();
-        propositions.push(string);
-        retrieve_possible_strings_from_tuples(tuples, propositions, nb_words, is_sentence_valid)
+/// Sharing a space is worth more than sharing a letter, every extra space may split a word
+fn match_score(letter: char) -> usize {
+    if letter == ' ' {
+        2
     } else {
-        let mut other_propositions: Vec<String> =
-            retrieve_possible_strings_from_tuple(tuple, propositions, nb_words, is_sentence_valid);
-        retrieve_possible_strings_from_tuples(
-            tuples,
-            &mut other_propositions,
-            nb_words,
-            is_sentence_valid,
-        )
-    };
+        1
+    }
 }
 
-fn retrieve_possible_strings_from_tuple(
-    tuple: Vec<char>,
-    propositions: &Vec<String>,
-    nb_words: &usize,
-    is_sentence_valid: &bool,
-) -> Vec<String> {
-    let mut other_propositions: Vec<String> = Vec::new();
-    propositions.iter().for_each(|proposition| {
-        let mut new_propositions =
-            retrieve_possible_strings_from_string(&tuple, proposition, nb_words, is_sentence_valid);
-        other_propositions.append(&mut new_propositions);
-    });
-    other_propositions
-}
+/// Splits the ordered letters in `word_count` words, adding spaces from the first letters
+pub fn insert_spaces(letters: &[char], word_count: usize) -> Result<String, RecoverSecretError> {
+    let sentence: String = letters.iter().collect();
+    let found = sentence.split_whitespace().count();
+    if found > word_count {
+        return Err(RecoverSecretError::TooManyWords { word_count, found });
+    }
+    let gaps = letters
+        .windows(2)
+        .filter(|pair| pair[0] != ' ' && pair[1] != ' ')
+        .count();
+    let mut missing_spaces = word_count - found;
+    if missing_spaces > gaps {
+        return Err(RecoverSecretError::NotEnoughLetters {
+            word_count,
+            letters: letters.iter().filter(|letter| **letter != ' ').count(),
+        });
+    }
 
-fn retrieve_possible_strings_from_string(
-    tuple: &Vec<char>,
-    old_proposition: &String,
-    nb_words: &usize,
-    is_sentence_valid: &bool,
-) -> Vec<String> {
-    let mut propositions: Vec<String> = Vec::new();
-    propositions.push(old_proposition.clone());
-    let tuple_length = tuple.len();
-
-    tuple.iter().enumerate().for_each(|(index, current_char)| {
-        let mut new_propositions: Vec<String> = Vec::new();
-        match index {
-            0 => process_first_element_of_tuple(
-                tuple,
-                &nb_words,
-                &propositions,
-                tuple_length,
-                index,
-                &current_char,
-                &mut new_propositions,
-                is_sentence_valid,
-            ),
-            index_match if index_match == tuple_length - 1 => {
-                // last element, there is at least one element before
-                process_last_element_of_tuple(
-                    tuple,
-                    &nb_words,
-                    &propositions,
-                    index,
-                    &current_char,
-                    &mut new_propositions,
-                    is_sentence_valid,
-                );
-            }
-            _ => {
-                // middle element, there is at least one element before and one element after
-                process_middle_element_of_tuple(
-                    tuple,
-                    &nb_words,
-                    &propositions,
-                    index,
-                    &current_char,
-                    &mut new_propositions,
-                    is_sentence_valid,
-                )
-            }
+    let mut sentence = String::with_capacity(letters.len() + missing_spaces);
+    for (index, letter) in letters.iter().enumerate() {
+        sentence.push(*letter);
+        let next = letters.get(index + 1);
+        if missing_spaces > 0 && *letter != ' ' && next.is_some_and(|next| *next != ' ') {
+            sentence.push(' ');
+            missing_spaces -= 1;
         }
-        propositions = new_propositions;
-    });
-    propositions
+    }
+    Ok(sentence)
 }
 
-fn process_middle_element_of_tuple(
-    // problem is when future seq is present but not fully yet
-    tuple: &Vec<char>,
-    nb_words: &usize,
-    propositions: &Vec<String>,
-    index: usize,
-    current_char: &char,
-    mut new_propositions: &mut Vec<String>,
-    compute_all_possibilities: &bool,
-) {
-    let next_char = tuple
-        .get(index + 1)
-        .expect("Unable to get next char in tuple middle char");
-    let previous_sequence: Vec<char> = tuple.iter().take(index).map(|c| *c).collect();
-
-    propositions.iter().for_each(|proposition| {
-        let mut new_proposition: String;
-        let is_current_char_present;
-
-        let is_next_char_present;
-        new_proposition = get_string_after_vec_sequence(proposition, &previous_sequence);
-
-        is_next_char_present = is_present(&new_proposition, next_char);
-
-        if is_next_char_present && (*next_char != ' ' || word_count(proposition) == *nb_words) {
-            new_proposition = get_string_before_last_occurrence(&new_proposition, next_char);
-        }
+/// Depth first search of the sentence, one letter at a time. A letter expected by a tuple
+/// advances every tuple expecting it, while the current word follows the paths of the trie:
+/// a sentence stops as soon as its last word is not a dictionary prefix. Spaces missing from
+/// the tuples are added between two whole words.
+///
+/// Repeated letters may leave a letter of the sentence expected by no tuple anymore, so a
+/// few extra letters are allowed, one more at each pass. The first passes only follow
+/// letters next to each other in a tuple, as in the challenges of the server where every
+/// two neighbouring letters of the sentence share a tuple.
+struct SentenceSearch<'a> {
+    tuples: &'a [Vec<char>],
+    dictionary: &'a Trie,
+    word_count: usize,
+    /// Letters of every tuple already placed in the sentence
+    progress: Vec<usize>,
+    sentence: Vec<char>,
+    /// Letters of the tuples, spaces excluded
+    alphabet: Vec<char>,
+    /// Pairs of letters next to each other in a tuple
+    neighbours: HashSet<(char, char)>,
+    only_neighbours: bool,
+    /// Most extra letters a state was explored with, without success
+    dead_ends: HashMap<(Vec<usize>, usize, usize), usize>,
+    /// Dead ends found by every pass
+    dead_end_count: usize,
+}
 
-        is_current_char_present = is_present(&new_proposition, current_char);
-
-        if !is_current_char_present {
-            if *compute_all_possibilities {
-                if !(is_next_char_present && next_char == current_char) {
-                    for i in 0..new_proposition.len() + 1 {
-                        if i == 0
-                            && new_proposition.len() > 0
-                            && new_proposition
-                                .chars()
-                                .nth(0)
-                                .expect("Unable to get first character of string")
-                                .is_uppercase()
-                        {
-                            continue;
-                        }
-                        let final_proposition =
-                            add_char_at_index(&new_proposition, &current_char, &i);
-                        push_proposition_with_string_between_chars(
-                            &mut new_propositions,
-                            &previous_sequence,
-                            next_char,
-                            &is_next_char_present,
-                            proposition,
-                            &final_proposition,
-                            &nb_words,
-                        );
-                    }
-                    // new_propositions.push(proposition.clone()); resolve issue but too much complexity....
-                } else {
-                    new_propositions.push(proposition.clone());
-                }
-            } else {
-                new_proposition.push(*current_char);
-                push_proposition_with_string_between_chars(
-                    &mut new_propositions,
-                    &previous_sequence,
-                    next_char,
-                    &is_next_char_present,
-                    proposition,
-                    &new_proposition,
-                    &nb_words,
-                );
-            }
-        } else {
-            new_propositions.push(proposition.clone());
+impl<'a> SentenceSearch<'a> {
+    fn new(tuples: &'a [Vec<char>], dictionary: &'a Trie, word_count: usize) -> Self {
+        let alphabet: BTreeSet<char> = tuples
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|letter| *letter != ' ')
+            .collect();
+        SentenceSearch {
+            tuples,
+            dictionary,
+            word_count,
+            progress: vec![0; tuples.len()],
+            sentence: Vec::new(),
+            alphabet: alphabet.into_iter().collect(),
+            neighbours: tuples
+                .iter()
+                .flat_map(|tuple| tuple.windows(2).map(|pair| (pair[0], pair[1])))
+                .collect(),
+            only_neighbours: true,
+            dead_ends: HashMap::new(),
+            dead_end_count: 0,
         }
-    })
-}
+    }
 
-fn process_last_element_of_tuple(
-    tuple: &Vec<char>,
-    nb_words: &usize,
-    propositions: &Vec<String>,
-    index: usize,
-    current_char: &char,
-    mut new_propositions: &mut Vec<String>,
-    compute_all_possibilities: &bool,
-) {
-    let previous_sequence: Vec<char> = tuple.iter().take(index).map(|c| *c).collect();
-    // println!("Current vec : {:?}", tuple);
-    propositions.iter().for_each(|proposition| {
-        let mut new_proposition = get_string_after_vec_sequence(proposition, &previous_sequence);
-        // let is_previous_char_present = is_present(proposition, previous_char);
-        let is_current_char_present = is_present(&new_proposition, current_char);
-
-        if !is_current_char_present {
-            if *compute_all_possibilities {
-                for i in 0..new_proposition.len() + 1 {
-                    if i == 0
-                        && new_proposition.len() > 0
-                        && new_proposition
-                            .chars()
-                            .nth(0)
-                            .expect("Unable to get first character of string")
-                            .is_uppercase()
-                    {
-                        continue;
-                    }
-                    let final_proposition = add_char_at_index(&new_proposition, &current_char, &i);
-                    push_proposition_with_string_before_char(
-                        &mut new_propositions,
-                        &previous_sequence,
-                        proposition,
-                        &final_proposition,
-                        &nb_words,
-                    );
+    fn run(mut self) -> Option<String> {
+        for only_neighbours in [true, false] {
+            self.only_neighbours = only_neighbours;
+            self.dead_ends.clear();
+            for extra_letters in 0..=MAX_EXTRA_LETTERS {
+                if self.search(Trie::ROOT, 0, extra_letters) {
+                    return Some(self.sentence.iter().collect());
                 }
-            } else {
-                new_proposition.push(*current_char);
-                push_proposition_with_string_before_char(
-                    &mut new_propositions,
-                    &previous_sequence,
-                    proposition,
-                    &new_proposition,
-                    &nb_words,
-                );
             }
-            // new_proposition.push(*current_char);
-        } else {
-            new_propositions.push(proposition.clone());
         }
-    });
-}
-
-fn process_first_element_of_tuple(
-    tuple: &Vec<char>,
-    nb_words: &usize,
-    propositions: &Vec<String>,
-    tuple_length: usize,
-    index: usize,
-    current_char: &char,
-    new_propositions: &mut Vec<String>,
-    compute_all_possibilities: &bool,
-) {
-    if tuple_length > 1 {
-        // there is at least one element after
-        process_first_element_of_tuple_more_than_one_element(
-            tuple,
-            nb_words,
-            propositions,
-            index,
-            current_char,
-            new_propositions,
-            compute_all_possibilities,
-        );
-    } else {
-        // single tuple, insert wherever we want if not present
-        process_first_element_of_tuple_one_element(
-            propositions,
-            current_char,
-            new_propositions,
-            compute_all_possibilities,
-        );
+        None
     }
-}
 
-fn process_first_element_of_tuple_one_element(
-    propositions: &Vec<String>,
-    current_char: &char,
-    new_propositions: &mut Vec<String>,
-    compute_all_possibilities: &bool,
-) {
-    propositions.iter().for_each(|proposition| {
-        let mut new_proposition = proposition.clone();
-        if !is_present(&new_proposition, current_char) {
-            if *compute_all_possibilities {
-                if current_char.is_uppercase() {
-                    new_propositions.push(add_char_at_index(&proposition, current_char, &0))
-                } else {
-                    for i in 0..new_proposition.len() + 1 {
-                        if i == 0
-                            && new_proposition.len() > 0
-                            && new_proposition
-                                .chars()
-                                .nth(0)
-                                .expect("Unable to get first character of string")
-                                .is_uppercase()
-                        {
-                            continue;
-                        }
-                        new_propositions.push(add_char_at_index(&new_proposition, current_char, &i))
-                    }
+    /// `node` is the end of the current word in the trie, `words` the words before it
+    fn search(&mut self, node: usize, words: usize, extra_letters: usize) -> bool {
+        if self.dead_end_count >= MAX_DEAD_ENDS {
+            return false;
+        }
+        let expected = self.expected_letters();
+        if expected.is_empty() && words + 1 == self.word_count && self.dictionary.is_word(node) {
+            return true;
+        }
+        // Every space still expected by a tuple starts one more word
+        let expected_spaces = self
+            .tuples
+            .iter()
+            .zip(&self.progress)
+            .map(|(tuple, done)| {
+                tuple[*done..]
+                    .iter()
+                    .filter(|letter| **letter == ' ')
+                    .count()
+            })
+            .max()
+            .unwrap_or(0);
+        if words + 1 + expected_spaces > self.word_count {
+            return false;
+        }
+        let state = (self.progress.clone(), node, words);
+        if self
+            .dead_ends
+            .get(&state)
+            .is_some_and(|explored| *explored >= extra_letters)
+        {
+            return false;
+        }
+
+        for letter in expected.iter().copied() {
+            for (next_node, next_words) in self.transitions(node, words, letter) {
+                let advanced = self.advance(letter);
+                self.sentence.push(letter);
+                if self.search(next_node, next_words, extra_letters) {
+                    return true;
                 }
-            } else {
-                new_proposition.push(*current_char);
-                new_propositions.push(new_proposition);
+                self.sentence.pop();
+                advanced
+                    .into_iter()
+                    .for_each(|index| self.progress[index] -= 1);
             }
-        } else {
-            new_propositions.push(new_proposition);
         }
-    });
-}
-
-fn process_first_element_of_tuple_more_than_one_element(
-    tuple: &Vec<char>,
-    nb_words: &usize,
-    propositions: &Vec<String>,
-    index: usize,
-    current_char: &char,
-    mut new_propositions: &mut Vec<String>,
-    compute_all_possibilities: &bool,
-) {
-    let next_char = tuple.get(index + 1).expect("Unable to get next character");
-
-    propositions.iter().for_each(|proposition| {
-        let mut new_proposition: String;
-        let is_next_char_present = is_present(proposition, next_char);
-        let is_current_char_present;
-
-        if !is_next_char_present {
-            new_proposition = proposition.clone();
-        } else {
-            new_proposition = get_string_before_last_occurrence(proposition, next_char);
+        let mut others: Vec<char> = Vec::new();
+        if !expected.contains(&' ') && words + 1 + expected_spaces < self.word_count {
+            others.push(' ');
         }
-
-        is_current_char_present = is_present(&new_proposition, current_char);
-
-        if !is_current_char_present {
-            if *compute_all_possibilities {
-                if current_char.is_uppercase() {
-                    new_propositions.push(add_char_at_index(&proposition, current_char, &0))
-                } else {
-                    for i in 0..new_proposition.len() + 1 {
-                        if i == 0
-                            && new_proposition.len() > 0
-                            && new_proposition
-                                .chars()
-                                .nth(0)
-                                .expect("Unable to get first character of string")
-                                .is_uppercase()
-                        {
-                            continue;
-                        }
-                        let final_proposition =
-                            add_char_at_index(&new_proposition, &current_char, &i);
-                        push_proposition_with_string_after_char(
-                            &mut new_propositions,
-                            &next_char,
-                            &is_next_char_present,
-                            &proposition,
-                            &final_proposition,
-                            &nb_words,
-                        );
-                    }
+        if extra_letters > 0 {
+            others.extend(
+                self.alphabet
+                    .iter()
+                    .filter(|letter| !expected.contains(letter)),
+            );
+        }
+        for letter in others {
+            let extra_letters = extra_letters - (letter != ' ') as usize;
+            for (next_node, next_words) in self.transitions(node, words, letter) {
+                self.sentence.push(letter);
+                if self.search(next_node, next_words, extra_letters) {
+                    return true;
                 }
-            } else {
-                new_proposition.push(*current_char);
-                push_proposition_with_string_after_char(
-                    &mut new_propositions,
-                    &next_char,
-                    &is_next_char_present,
-                    &proposition,
-                    &new_proposition,
-                    &nb_words,
-                );
+                self.sentence.pop();
             }
-        } else {
-            new_propositions.push(proposition.clone());
         }
-    });
-}
-
-fn push_proposition_with_string_between_chars(
-    new_propositions: &mut Vec<String>,
-    previous_sequence: &Vec<char>,
-    next_char: &char,
-    is_next_char_present: &bool,
-    proposition: &String,
-    new_proposition: &String,
-    nb_words: &usize,
-) {
-    let mut final_proposition =
-        get_string_before_vec_sequence_inclusive(proposition, &previous_sequence);
-    final_proposition.push_str(&new_proposition);
-
-    if *is_next_char_present && (*next_char != ' ' || word_count(proposition) == *nb_words) {
-        let tmp = get_string_after_last_occurrence(proposition, next_char);
-        final_proposition.push(*next_char);
-        final_proposition.push_str(&tmp);
+        self.dead_ends.insert(state, extra_letters);
+        self.dead_end_count += 1;
+        false
     }
-    // if proposition.contains("Il ") {
-    //     println!("'{}' -> '{}'\nNext char : '{}'", proposition, final_proposition, next_char);
-    // }
 
-    if word_count(&final_proposition) <= *nb_words {
-        new_propositions.push(final_proposition);
+    /// Next letter of every unfinished tuple, without duplicates
+    fn expected_letters(&self) -> Vec<char> {
+        let mut letters: Vec<(char, usize)> = Vec::new();
+        for (tuple, done) in self.tuples.iter().zip(&self.progress) {
+            if let Some(letter) = tuple.get(*done) {
+                match letters.iter_mut().find(|(other, _)| other == letter) {
+                    Some((_, count)) => *count += 1,
+                    None => letters.push((*letter, 1)),
+                }
+            }
+        }
+        letters.sort_by_key(|(_, count)| Reverse(*count));
+        letters.into_iter().map(|(letter, _)| letter).collect()
     }
-}
 
-fn push_proposition_with_string_before_char(
-    new_propositions: &mut Vec<String>,
-    previous_sequence: &Vec<char>,
-    proposition: &String,
-    new_proposition: &String,
-    nb_words: &usize,
-) {
-    let mut final_proposition =
-        get_string_before_vec_sequence_inclusive(proposition, &previous_sequence);
-
-    final_proposition.push_str(new_proposition);
-    // println!("'{}' -> '{}'", proposition, final_proposition);
-    if word_count(&final_proposition) <= *nb_words {
-        new_propositions.push(final_proposition);
+    /// Trie nodes and word counts reached with `letter` after the current sentence
+    fn transitions(&self, node: usize, words: usize, letter: char) -> Vec<(usize, usize)> {
+        let is_neighbour = self.sentence.last().is_none_or(|previous| {
+            !self.only_neighbours || self.neighbours.contains(&(*previous, letter))
+        });
+        if !is_neighbour {
+            Vec::new()
+        } else if letter == ' ' {
+            if self.dictionary.is_word(node) {
+                vec![(Trie::ROOT, words + 1)]
+            } else {
+                Vec::new()
+            }
+        } else {
+            self.dictionary
+                .next_nodes(node, letter)
+                .into_iter()
+                .map(|next_node| (next_node, words))
+                .collect()
+        }
     }
-}
 
-fn push_proposition_with_string_after_char(
-    new_propositions: &mut Vec<String>,
-    next_char: &char,
-    is_next_char_present: &bool,
-    proposition: &String,
-    new_proposition: &String,
-    nb_words: &usize,
-) {
-    let mut final_proposition = new_proposition.clone();
-
-    if *is_next_char_present {
-        final_proposition.push(*next_char);
-        final_proposition.push_str(&get_string_after_last_occurrence(proposition, next_char));
-    }
-    // println!("'{}' -> '{}'", proposition, final_proposition);
-    if word_count(&final_proposition) <= *nb_words {
-        new_propositions.push(final_proposition);
+    /// Returns the tuples advanced by `letter`
+    fn advance(&mut self, letter: char) -> Vec<usize> {
+        let mut advanced = Vec::new();
+        for (index, tuple) in self.tuples.iter().enumerate() {
+            if tuple.get(self.progress[index]) == Some(&letter) {
+                self.progress[index] += 1;
+                advanced.push(index);
+            }
+        }
+        advanced
     }
 }
 
-fn find_sentence(possibilities: &Vec<String>, dictionary: &HashSet<String>) -> String {
-    // println!("Searching for a sentence...");
-    let mut invalid_words: HashSet<String> = HashSet::new();
-    for possibility in possibilities {
-        let words: HashSet<String> = possibility
-            .to_lowercase()
-            .split(|c: char| c == ' ' || c == '-')
-            .map(|s| s.to_string())
-            .collect();
-
-        if words.intersection(&invalid_words).count() > 0 {
-            continue;
-        }
-
-        let wrong_words: HashSet<String> = words
-            .difference(dictionary)
-            .map(|s| s.to_string())
-            .collect();
-
-        if wrong_words.len() > 0 {
-            wrong_words.iter().for_each(|wrong_word| {
-                invalid_words.insert(wrong_word.clone());
-            });
-            continue;
-        }
-
-        return possibility.clone();
-    }
-    panic!("No sentence found");
+pub fn retrieve_tuples_from_letters(input: &RecoverSecretInput) -> Vec<Vec<char>> {
+    let mut letters = input.letters.chars();
+    input
+        .tuple_sizes
+        .iter()
+        .map(|size| {
+            let tuple: Vec<char> = letters.by_ref().take(*size).collect();
+            assert_eq!(tuple.len(), *size, "Unable to get char for tuple");
+            tuple
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use utils::file_utils::read_file;
-    use utils::string_utils::generate_dictionary_hashmap;
 
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::challenge_generator::{
+        derive_tuples, dictionary_words, generate_secret_challenge, validate_challenge,
+        SecretConfig, SentenceSource,
+    };
     use crate::challenge_resolve::{
-        solve_secret_sentence_challenge, solve_secret_string_challenge,
+        insert_spaces, solve_secret_sentence_challenge, solve_secret_string_challenge,
     };
-    use crate::models::RecoverSecretInput;
+    use crate::dictionary::Trie;
+    use crate::models::{RecoverSecretError, RecoverSecretInput};
 
     #[test]
     fn test_solve_secret_sentence_challenge() {
         let dictionary = read_file("data-test/liste-de-ses-morts.dic");
-        let dictionary_hashmap = generate_dictionary_hashmap(&dictionary);
+        let dictionary = Trie::from_words(dictionary.split_whitespace());
 
         let recover_secret_input: RecoverSecretInput = RecoverSecretInput {
             word_count: 2,
@@ -594,7 +488,7 @@ mod tests {
             tuple_sizes: vec![5, 6, 5, 4, 2, 4, 5],
         };
 
-        let answer = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+        let answer = solve_secret_sentence_challenge(&recover_secret_input, &dictionary).unwrap();
         assert_eq!(answer.secret_sentence, "C'est chou".to_string());
 
         let recover_secret_input: RecoverSecretInput = RecoverSecretInput {
@@ -603,7 +497,7 @@ mod tests {
             tuple_sizes: vec![6, 6, 6],
         };
 
-        let answer = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+        let answer = solve_secret_sentence_challenge(&recover_secret_input, &dictionary).unwrap();
         assert_eq!(answer.secret_sentence, "Il fait froid".to_string());
 
         let recover_secret_input: RecoverSecretInput = RecoverSecretInput {
@@ -612,7 +506,7 @@ mod tests {
             tuple_sizes: vec![8, 3, 4, 4, 6, 5, 4, 7, 7],
         };
 
-        let answer = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+        let answer = solve_secret_sentence_challenge(&recover_secret_input, &dictionary).unwrap();
         assert_eq!(answer.secret_sentence, "Il fait froid".to_string());
 
         let recover_secret_input: RecoverSecretInput = RecoverSecretInput {
@@ -621,7 +515,7 @@ mod tests {
             tuple_sizes: vec![7, 4, 3, 5, 6, 5, 5, 4, 5],
         };
 
-        let answer = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+        let answer = solve_secret_sentence_challenge(&recover_secret_input, &dictionary).unwrap();
         assert_eq!(answer.secret_sentence, "Il fait froid".to_string());
     }
 
@@ -633,8 +527,9 @@ mod tests {
             tuple_sizes: vec![3, 3, 3, 3, 3, 3],
         };
 
-        let answer = solve_secret_string_challenge(&recover_secret_input);
-        assert_eq!(answer.secret_sentence, "iiriflfatrod".to_string());
+        let answer = solve_secret_string_challenge(&recover_secret_input).unwrap();
+        assert!(validate_challenge(&recover_secret_input, &answer));
+        assert_eq!(answer.secret_sentence.len(), "iiriflfatrod".len());
     }
 
     #[test]
@@ -645,8 +540,9 @@ mod tests {
             tuple_sizes: vec![3, 3, 3],
         };
 
-        let answer = solve_secret_string_challenge(&recover_secret_input);
-        assert_eq!(answer.secret_sentence, "rTlhzo".to_string());
+        let answer = solve_secret_string_challenge(&recover_secret_input).unwrap();
+        assert!(validate_challenge(&recover_secret_input, &answer));
+        assert_eq!(answer.secret_sentence.len(), "rTlhzo".len());
     }
 
     #[test]
@@ -657,7 +553,141 @@ mod tests {
             tuple_sizes: vec![3, 3, 3, 3, 3, 3],
         };
 
-        let answer = solve_secret_string_challenge(&recover_secret_input);
-        assert_eq!(answer.secret_sentence, "i i r i f lfatrod".to_string());
+        let answer = solve_secret_string_challenge(&recover_secret_input).unwrap();
+        assert!(validate_challenge(&recover_secret_input, &answer));
+    }
+
+    #[test]
+    fn test_solve_secret_string_challenge_distinct_letters() {
+        let recover_secret_input: RecoverSecretInput = RecoverSecretInput {
+            word_count: 2,
+            letters: "c dabcb edeF".to_string(),
+            tuple_sizes: vec![3, 3, 3, 3],
+        };
+
+        let answer = solve_secret_string_challenge(&recover_secret_input).unwrap();
+        assert_eq!(answer.secret_sentence, "abc deF");
+    }
+
+    #[test]
+    fn test_solve_secret_string_challenge_repeated_letters() {
+        let recover_secret_input: RecoverSecretInput = RecoverSecretInput {
+            word_count: 3,
+            letters: " it fridft Ilfrlafdl tfidatrodliidIl fridIlft od".to_string(),
+            tuple_sizes: vec![8, 3, 4, 4, 6, 5, 4, 7, 7],
+        };
+
+        let answer = solve_secret_string_challenge(&recover_secret_input).unwrap();
+        assert!(validate_challenge(&recover_secret_input, &answer));
+    }
+
+    #[test]
+    fn test_solve_generated_challenges() {
+        let mut rng = StdRng::seed_from_u64(14);
+        for complexity in 0..=21 {
+            let config = SecretConfig::from_complexity(complexity);
+            for _ in 0..30 {
+                let challenge = generate_secret_challenge(&config, &mut rng);
+                let answer = solve_secret_string_challenge(&challenge.input).unwrap();
+                assert!(validate_challenge(&challenge.input, &answer));
+                if let SentenceSource::RandomLetters { .. } = config.source {
+                    if challenge.input.word_count <= 2 {
+                        // Letters and the only space are all different, the tuples only allow
+                        // the secret
+                        assert_eq!(answer.secret_sentence, challenge.secret_sentence);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_secret_string_challenge_errors() {
+        let input =
+            |word_count: usize, letters: &str, tuple_sizes: Vec<usize>| RecoverSecretInput {
+                word_count,
+                letters: letters.to_string(),
+                tuple_sizes,
+            };
+        assert_eq!(
+            solve_secret_string_challenge(&input(1, "abcd", vec![3])).unwrap_err(),
+            RecoverSecretError::InvalidTupleSizes {
+                letters: 4,
+                tuple_sizes: 3
+            }
+        );
+        assert_eq!(
+            solve_secret_string_challenge(&input(2, "a b c", vec![5])).unwrap_err(),
+            RecoverSecretError::TooManyWords {
+                word_count: 2,
+                found: 3
+            }
+        );
+        assert_eq!(
+            solve_secret_string_challenge(&input(3, "ab", vec![2])).unwrap_err(),
+            RecoverSecretError::NotEnoughLetters {
+                word_count: 3,
+                letters: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_insert_spaces() {
+        let letters: Vec<char> = "abc d".chars().collect();
+        assert_eq!(insert_spaces(&letters, 2), Ok("abc d".to_string()));
+        assert_eq!(insert_spaces(&letters, 3), Ok("a bc d".to_string()));
+        assert_eq!(insert_spaces(&letters, 4), Ok("a b c d".to_string()));
+        assert!(insert_spaces(&letters, 5).is_err());
+        assert!(insert_spaces(&letters, 1).is_err());
+        assert_eq!(insert_spaces(&[], 0), Ok(String::new()));
+    }
+
+    #[test]
+    fn test_solve_generated_dictionary_challenges() {
+        let dictionary = Trie::from_words(dictionary_words());
+        let mut rng = StdRng::seed_from_u64(15);
+        for complexity in 18..=19 {
+            let config = SecretConfig::from_complexity(complexity);
+            for _ in 0..20 {
+                let challenge = generate_secret_challenge(&config, &mut rng);
+                let answer = solve_secret_sentence_challenge(&challenge.input, &dictionary)
+                    .unwrap_or_else(|err| panic!("{:?}: {}", challenge.secret_sentence, err));
+                assert!(validate_challenge(&challenge.input, &answer));
+                assert!(answer
+                    .secret_sentence
+                    .split(' ')
+                    .all(|word| dictionary.accepts(word)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_secret_sentence_challenge_joined_words() {
+        let dictionary = Trie::from_words(["esprit", "quatre", "vingt", "deux", "corps"]);
+        let mut rng = StdRng::seed_from_u64(15);
+        let secret_sentence = "l'esprit quatre-vingt-deux";
+        let tuples = derive_tuples(secret_sentence, 3..=4, &mut rng);
+        let input = RecoverSecretInput {
+            word_count: 2,
+            letters: tuples.iter().flatten().collect(),
+            tuple_sizes: tuples.iter().map(|tuple| tuple.len()).collect(),
+        };
+        let answer = solve_secret_sentence_challenge(&input, &dictionary).unwrap();
+        assert_eq!(answer.secret_sentence, secret_sentence);
+    }
+
+    #[test]
+    fn test_solve_secret_sentence_challenge_without_sentence() {
+        let dictionary = Trie::from_words(["il", "fait", "froid"]);
+        let input = RecoverSecretInput {
+            word_count: 2,
+            letters: "xyzyzw".to_string(),
+            tuple_sizes: vec![3, 3],
+        };
+        assert_eq!(
+            solve_secret_sentence_challenge(&input, &dictionary).unwrap_err(),
+            RecoverSecretError::NoSentenceFound { word_count: 2 }
+        );
     }
 }
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+/// Elided forms followed by an apostrophe in front of the next word, as in `l'esprit`
+pub const ELIDED_WORDS: [&str; 12] = [
+    "c", "d", "j", "l", "m", "n", "s", "t", "qu", "jusqu", "lorsqu", "puisqu",
+];
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: BTreeMap<char, usize>,
+    is_word: bool,
+    is_elided: bool,
+}
+
+/// Prefix tree of the dictionary words, letters are stored lowercase
+#[derive(Debug, Clone)]
+pub struct Trie {
+    nodes: Vec<TrieNode>,
+}
+
+impl Trie {
+    pub const ROOT: usize = 0;
+
+    pub fn new() -> Trie {
+        Trie {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+
+    /// Builds the trie of the words and of the French elided forms
+    pub fn from_words<I, S>(words: I) -> Trie
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut trie = Trie::new();
+        for word in words {
+            trie.insert(word.as_ref());
+        }
+        for word in ELIDED_WORDS {
+            let node = trie.insert_prefix(word);
+            trie.nodes[node].is_elided = true;
+        }
+        trie
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        let node = self.insert_prefix(word);
+        self.nodes[node].is_word = true;
+    }
+
+    fn insert_prefix(&mut self, prefix: &str) -> usize {
+        let mut node = Trie::ROOT;
+        for letter in prefix.chars().flat_map(char::to_lowercase) {
+            node = match self.nodes[node].children.get(&letter) {
+                Some(child) => *child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(letter, child);
+                    child
+                }
+            };
+        }
+        node
+    }
+
+    /// Node reached from `node` with `letter`, whatever its case
+    pub fn child(&self, node: usize, letter: char) -> Option<usize> {
+        letter.to_lowercase().try_fold(node, |node, letter| {
+            self.nodes[node].children.get(&letter).copied()
+        })
+    }
+
+    /// Nodes reached from `node` with `letter`: its child, and the root when the letter joins
+    /// two parts of a word, a hyphen after a whole word or an apostrophe after an elided one
+    pub fn next_nodes(&self, node: usize, letter: char) -> Vec<usize> {
+        let mut nodes: Vec<usize> = self.child(node, letter).into_iter().collect();
+        let is_joined = match letter {
+            '-' => self.is_word(node),
+            '\'' => self.is_elided(node),
+            _ => false,
+        };
+        if is_joined {
+            nodes.push(Trie::ROOT);
+        }
+        nodes
+    }
+
+    /// Whether the word is a dictionary word or dictionary words joined like in
+    /// `quatre-vingt-treize` or `l'esprit`
+    pub fn accepts(&self, word: &str) -> bool {
+        let mut nodes = vec![Trie::ROOT];
+        for letter in word.chars() {
+            nodes = nodes
+                .into_iter()
+                .flat_map(|node| self.next_nodes(node, letter))
+                .collect();
+            nodes.dedup();
+        }
+        nodes.into_iter().any(|node| self.is_word(node))
+    }
+
+    /// Letters following `node` in at least one word
+    pub fn letters(&self, node: usize) -> impl Iterator<Item = char> + '_ {
+        self.nodes[node].children.keys().copied()
+    }
+
+    /// Whether the path to `node` spells a whole word
+    pub fn is_word(&self, node: usize) -> bool {
+        self.nodes[node].is_word
+    }
+
+    /// Whether the path to `node` is an elided form expecting an apostrophe
+    pub fn is_elided(&self, node: usize) -> bool {
+        self.nodes[node].is_elided
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.find(word).is_some_and(|node| self.is_word(node))
+    }
+
+    pub fn is_prefix(&self, prefix: &str) -> bool {
+        self.find(prefix).is_some()
+    }
+
+    fn find(&self, prefix: &str) -> Option<usize> {
+        prefix
+            .chars()
+            .try_fold(Trie::ROOT, |node, letter| self.child(node, letter))
+    }
+}
+
+impl Default for Trie {
+    fn default() -> Self {
+        Trie::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trie() {
+        let trie = Trie::from_words(["il", "fait", "faire", "quatre-vingt"]);
+        assert!(trie.contains("il"));
+        assert!(trie.contains("Il"));
+        assert!(trie.contains("quatre-vingt"));
+        assert!(!trie.contains("fai"));
+        assert!(trie.is_prefix("fai"));
+        assert!(!trie.is_prefix("froid"));
+        assert!(!trie.contains("l"));
+
+        let node = trie.child(Trie::ROOT, 'L').unwrap();
+        assert!(trie.is_elided(node));
+        let node = trie.child(Trie::ROOT, 'f').unwrap();
+        assert_eq!(trie.letters(node).collect::<Vec<_>>(), vec!['a']);
+    }
+}
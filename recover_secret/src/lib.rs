@@ -1,3 +1,4 @@
 pub mod challenge_generator;
 pub mod challenge_resolve;
+pub mod dictionary;
 pub mod models;
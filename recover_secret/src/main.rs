@@ -1,16 +1,16 @@
 use std::time::Instant;
 
 use recover_secret::challenge_resolve::solve_secret_sentence_challenge;
+use recover_secret::dictionary::Trie;
 use recover_secret::models::RecoverSecretInput;
 use utils::file_utils::read_file;
-use utils::string_utils::generate_dictionary_hashmap;
 
 fn main() {
     println!("Reading dictionary file...");
     let dictionary = read_file("data/liste-de-ses-morts.dic");
-    println!("Generating hashmap...");
+    println!("Generating trie...");
     let i = Instant::now();
-    let dictionary_hashmap = generate_dictionary_hashmap(&dictionary);
+    let dictionary_trie = Trie::from_words(dictionary.split_whitespace());
     println!("Done !");
     println!("{:?}", i.elapsed());
 
@@ -26,7 +26,7 @@ fn main() {
         recover_secret_input
     );
     let i = Instant::now();
-    let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+    let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_trie);
     println!("{:?}", output);
     println!("{:?}", i.elapsed());*/
 
@@ -84,7 +84,7 @@ fn main() {
             recover_secret_input
         );
         let i = Instant::now();
-        let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+        let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_trie);
         println!("{:?}", output);
         println!("{:?}", i.elapsed());
 
@@ -101,7 +101,7 @@ fn main() {
             recover_secret_input
         );
         let i = Instant::now();
-        let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+        let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_trie);
         println!("{:?}", output);
         println!("{:?}", i.elapsed());
 
@@ -118,7 +118,7 @@ fn main() {
             recover_secret_input
         );
         let i = Instant::now();
-        let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+        let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_trie);
         println!("{:?}", output);
         println!("{:?}", i.elapsed());
     */
@@ -137,7 +137,7 @@ fn main() {
         recover_secret_input
     );
     let i = Instant::now();
-    let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_hashmap);
+    let output = solve_secret_sentence_challenge(&recover_secret_input, &dictionary_trie);
     println!("{:?}", output);
     println!("{:?}", i.elapsed());
 }
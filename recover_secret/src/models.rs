@@ -14,3 +14,57 @@ pub struct RecoverSecretOutput {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RecoverSecret(pub RecoverSecretInput);
+
+/// Generated challenge with the sentence it hides, only the input is sent to the players
+#[derive(Debug, Clone)]
+pub struct RecoverSecretChallenge {
+    pub input: RecoverSecretInput,
+    pub secret_sentence: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoverSecretError {
+    /// `tuple_sizes` does not add up to the number of letters
+    InvalidTupleSizes { letters: usize, tuple_sizes: usize },
+    /// The tuples separate more words than requested, they contradict the word count
+    TooManyWords { word_count: usize, found: usize },
+    /// Not enough letters to split them in the requested number of words
+    NotEnoughLetters { word_count: usize, letters: usize },
+    /// No sentence of dictionary words satisfies the tuples
+    NoSentenceFound { word_count: usize },
+}
+
+impl std::fmt::Display for RecoverSecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecoverSecretError::InvalidTupleSizes {
+                letters,
+                tuple_sizes,
+            } => write!(
+                f,
+                "tuple sizes add up to {} letters but {} letters are given",
+                tuple_sizes, letters
+            ),
+            RecoverSecretError::TooManyWords { word_count, found } => write!(
+                f,
+                "the tuples separate {} words but {} are expected",
+                found, word_count
+            ),
+            RecoverSecretError::NotEnoughLetters {
+                word_count,
+                letters,
+            } => write!(
+                f,
+                "{} letters cannot be split in {} words",
+                letters, word_count
+            ),
+            RecoverSecretError::NoSentenceFound { word_count } => write!(
+                f,
+                "no sentence of {} dictionary words satisfies the tuples",
+                word_count
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecoverSecretError {}
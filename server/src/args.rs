@@ -1,5 +1,9 @@
 use clap::Parser;
 
+use recover_secret::challenge_generator::DEFAULT_COMPLEXITY;
+
+use crate::rotation::GameMix;
+
 #[derive(Parser, Default, Debug)]
 #[clap(author, version, about, long_about = None)]
 pub struct ServerArgs {
@@ -15,11 +19,75 @@ pub struct ServerArgs {
     #[clap(short, long, value_parser, default_value = "info")]
     pub log_level: String,
 
-    /// Game type
-    #[clap(short, long, value_parser, default_value = "hash-cash", possible_values = &["hash-cash", "recover-secret", "monstrous-maze"])]
-    pub game_type: String,
+    /// Game types played, a single one or a weighted mix like `hash-cash:2,monstrous-maze:1`
+    #[clap(short, long, value_parser, default_value = "hash-cash")]
+    pub game_type: GameMix,
+
+    /// How the game types of a mix follow each other from round to round
+    #[clap(
+        long,
+        value_parser,
+        default_value = "round-robin",
+        possible_values = &["round-robin", "random", "escalating"]
+    )]
+    pub rotation: String,
 
     /// Round Duration in seconds
     #[clap(short, long, value_parser, default_value = "3")]
     pub round_duration: u64,
+
+    /// Time allowed to a player to answer a challenge, in milliseconds, at least 1
+    #[clap(long, value_parser = parse_challenge_timeout, default_value_t = 2000)]
+    pub challenge_timeout: u64,
+
+    /// Number of rounds played before the end of the game, at least 1
+    #[clap(long, value_parser = parse_rounds, default_value_t = 10)]
+    pub rounds: usize,
+
+    /// Complexity of the RecoverSecret challenges, levels are described in recover_secret.md
+    #[clap(long, value_parser, default_value_t = DEFAULT_COMPLEXITY)]
+    pub complexity: u8,
+
+    /// Adapts the difficulty of the challenges to the solve times of each player
+    #[clap(long, value_parser)]
+    pub adaptive_difficulty: bool,
+
+    /// Fraction of the challenge timeout the adaptive difficulty aims at, between 0 and 1
+    #[clap(long, value_parser = parse_target_ratio, default_value_t = 0.5)]
+    pub target_ratio: f64,
+
+    /// File the game events are appended to as JSON lines, read back by the `replay` binary
+    #[clap(long, value_parser)]
+    pub event_log: Option<String>,
+
+    /// Seed of the game, the same seed and players give the same challenges. Random by default
+    #[clap(long, value_parser)]
+    pub seed: Option<u64>,
+}
+
+fn parse_rounds(rounds: &str) -> Result<usize, String> {
+    match rounds.parse() {
+        Ok(0) => Err("a game plays at least 1 round".to_string()),
+        Ok(rounds) => Ok(rounds),
+        Err(_) => Err(format!("invalid number of rounds {:?}", rounds)),
+    }
+}
+
+fn parse_challenge_timeout(timeout: &str) -> Result<u64, String> {
+    match timeout.parse() {
+        Ok(0) => Err("a challenge lasts at least 1 millisecond".to_string()),
+        Ok(timeout) => Ok(timeout),
+        Err(_) => Err(format!("invalid challenge timeout {:?}", timeout)),
+    }
+}
+
+fn parse_target_ratio(ratio: &str) -> Result<f64, String> {
+    let ratio: f64 = ratio
+        .parse()
+        .map_err(|_| format!("invalid ratio {:?}", ratio))?;
+    if ratio > 0.0 && ratio <= 1.0 {
+        Ok(ratio)
+    } else {
+        Err(format!("{} is not in ]0, 1]", ratio))
+    }
 }
@@ -0,0 +1,427 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::process;
+
+use clap::Parser;
+
+use shared::challenge::{ChallengeAnswer, ChallengeType};
+use shared::event::{EventRecord, GameEvent, ScoreReason};
+use shared::public_player::PublicPlayer;
+use shared::registry::registry;
+
+/// Difference tolerated between a logged and a recomputed total time, in seconds
+const TIME_TOLERANCE: f64 = 1e-6;
+
+/// Re-verifies the answers of a game event log and recomputes its leaderboard
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct ReplayArgs {
+    /// Event log written by the server with `--event-log`
+    #[clap(value_parser)]
+    event_log: String,
+}
+
+/// Answer waiting for the verification result logged by the server
+#[derive(Debug)]
+struct ReplayedAnswer {
+    player: String,
+    passed: bool,
+    used_time: f64,
+}
+
+/// Leaderboard rebuilt for a game of the log
+#[derive(Debug)]
+struct ReplayedGame {
+    seed: Option<u64>,
+    players: Vec<PublicPlayer>,
+    has_end_of_game: bool,
+}
+
+/// Leaderboards rebuilt from the events, with every difference to what the server logged
+#[derive(Debug, Default)]
+struct Replay {
+    games: Vec<ReplayedGame>,
+    /// Seed of the game being replayed, `None` for a log written before `GameStarted` existed
+    seed: Option<u64>,
+    /// Events of the game being replayed were applied
+    has_events: bool,
+    players: Vec<PublicPlayer>,
+    pending_challenge: Option<(String, ChallengeType)>,
+    pending_answer: Option<ReplayedAnswer>,
+    verified_answers: usize,
+    logged_leader_board: Option<Vec<PublicPlayer>>,
+    discrepancies: Vec<String>,
+}
+
+impl Replay {
+    fn apply(&mut self, line: usize, event: GameEvent) {
+        if let GameEvent::GameStarted { seed } = event {
+            if self.has_events {
+                self.finish();
+            }
+            self.seed = Some(seed);
+            return;
+        }
+        self.has_events = true;
+        match event {
+            GameEvent::GameStarted { .. } => {}
+            GameEvent::PlayerJoined { name, stream_id } => {
+                let mut player = PublicPlayer::new(name.clone(), stream_id);
+                player.make_active(&name);
+                self.players.push(player);
+            }
+            GameEvent::RoundStarted { .. } => {}
+            GameEvent::ChallengeIssued {
+                player, challenge, ..
+            } => self.pending_challenge = Some((player, challenge)),
+            GameEvent::AnswerReceived {
+                player,
+                answer,
+                used_time,
+                ..
+            } => self.replay_answer(line, player, answer, used_time),
+            GameEvent::AnswerVerified { player, passed } => {
+                self.check_verification(line, player, passed)
+            }
+            GameEvent::ChallengeMissed { .. } => self.pending_challenge = None,
+            GameEvent::ScoreChanged {
+                player,
+                score,
+                reason,
+            } => self.check_score(line, player, score, reason),
+            GameEvent::RoundEnded { .. } => {
+                self.pending_challenge = None;
+                self.pending_answer = None;
+            }
+            GameEvent::GameEnded { leader_board } => self.logged_leader_board = Some(leader_board),
+        }
+    }
+
+    fn replay_answer(
+        &mut self,
+        line: usize,
+        player: String,
+        answer: ChallengeAnswer,
+        used_time: f64,
+    ) {
+        let passed = match self.pending_challenge.take() {
+            Some((target, challenge)) if target == player => registry().verify(&challenge, &answer),
+            _ => {
+                self.report(line, format!("answer of {:?} to no challenge", player));
+                false
+            }
+        };
+        self.pending_answer = Some(ReplayedAnswer {
+            player,
+            passed,
+            used_time,
+        });
+    }
+
+    fn check_verification(&mut self, line: usize, player: String, logged: bool) {
+        let answer = match self.pending_answer.take() {
+            Some(answer) if answer.player == player => answer,
+            _ => {
+                self.report(line, format!("verification of {:?} without answer", player));
+                return;
+            }
+        };
+        self.verified_answers += 1;
+        if answer.passed != logged {
+            self.report(
+                line,
+                format!(
+                    "answer of {:?} logged as passed={} but re-verified as passed={}",
+                    player, logged, answer.passed
+                ),
+            );
+        }
+        let entry = match self.player_mut(&player) {
+            Some(entry) => entry,
+            None => {
+                self.report(line, format!("unknown player {:?}", player));
+                return;
+            }
+        };
+        if answer.passed {
+            entry.steps += 1;
+            entry.total_used_time += answer.used_time;
+        } else {
+            entry.score -= 1;
+        }
+    }
+
+    /// Bad results were already penalized by the re-verification
+    fn check_score(&mut self, line: usize, player: String, logged: i32, reason: ScoreReason) {
+        let entry = match self.player_mut(&player) {
+            Some(entry) => entry,
+            None => {
+                self.report(line, format!("unknown player {:?}", player));
+                return;
+            }
+        };
+        if reason == ScoreReason::RoundExpired {
+            entry.score -= 1;
+        }
+        let score = entry.score;
+        if score != logged {
+            self.report(
+                line,
+                format!(
+                    "score of {:?} logged as {} but recomputed as {}",
+                    player, logged, score
+                ),
+            );
+        }
+    }
+
+    /// Closes the game being replayed: compares the recomputed leaderboard with the one logged
+    /// at the end of the game, then starts over for the next game of the log
+    fn finish(&mut self) {
+        let logged_leader_board = self.logged_leader_board.take();
+        let has_end_of_game = logged_leader_board.is_some();
+        for logged in logged_leader_board.unwrap_or_default() {
+            match self
+                .players
+                .iter()
+                .find(|player| player.name == logged.name)
+            {
+                Some(player) if !has_same_standing(player, &logged) => self.discrepancies.push(
+                    format!("leaderboard: logged {:?}, recomputed {:?}", logged, player),
+                ),
+                Some(_) => {}
+                None if logged.score != 0 || logged.steps != 0 => self
+                    .discrepancies
+                    .push(format!("leaderboard: {:?} never joined the game", logged)),
+                None => {}
+            }
+        }
+        self.games.push(ReplayedGame {
+            seed: self.seed.take(),
+            players: std::mem::take(&mut self.players),
+            has_end_of_game,
+        });
+        self.pending_challenge = None;
+        self.pending_answer = None;
+        self.has_events = false;
+    }
+
+    fn player_mut(&mut self, name: &str) -> Option<&mut PublicPlayer> {
+        self.players.iter_mut().find(|player| player.name == name)
+    }
+
+    fn report(&mut self, line: usize, discrepancy: String) {
+        self.discrepancies
+            .push(format!("line {}: {}", line, discrepancy));
+    }
+}
+
+fn has_same_standing(player: &PublicPlayer, other: &PublicPlayer) -> bool {
+    player.score == other.score
+        && player.steps == other.steps
+        && (player.total_used_time - other.total_used_time).abs() <= TIME_TOLERANCE
+}
+
+/// Replays every line of the log, a line that is not an event is a discrepancy
+fn replay<R: BufRead>(reader: R) -> Replay {
+    let mut replay = Replay::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                replay.report(line_number, format!("cannot read the line: {}", err));
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<EventRecord>(&line) {
+            Ok(record) => replay.apply(line_number, record.event),
+            Err(err) => replay.report(line_number, format!("invalid event: {}", err)),
+        }
+    }
+    replay
+}
+
+fn main() {
+    let args = ReplayArgs::parse();
+    let file = File::open(&args.event_log).unwrap_or_else(|err| {
+        eprintln!("Cannot open {:?}: {}", args.event_log, err);
+        process::exit(2);
+    });
+    let mut replay = replay(BufReader::new(file));
+    if replay.has_events || replay.seed.is_some() {
+        replay.finish();
+    }
+
+    println!("Answers re-verified: {}", replay.verified_answers);
+    for (index, game) in replay.games.iter_mut().enumerate() {
+        match game.seed {
+            Some(seed) => println!("Leaderboard of game {} (seed {}) :", index + 1, seed),
+            None => println!("Leaderboard of game {} :", index + 1),
+        }
+        game.players
+            .sort_by(|a, b| b.score.cmp(&a.score).then(b.steps.cmp(&a.steps)));
+        for player in &game.players {
+            println!("{:?}", player);
+        }
+        if !game.has_end_of_game {
+            println!("The game has no end of game, its final leaderboard is not checked");
+        }
+    }
+    if replay.discrepancies.is_empty() {
+        println!("No discrepancy");
+        return;
+    }
+    println!("{} discrepancies:", replay.discrepancies.len());
+    for discrepancy in &replay.discrepancies {
+        println!("  {}", discrepancy);
+    }
+    process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use shared::challenge::ChallengeValue;
+
+    use super::*;
+
+    fn line(event: GameEvent) -> String {
+        serde_json::to_string(&EventRecord { time: 0.0, event }).unwrap()
+    }
+
+    fn joined(name: &str) -> GameEvent {
+        GameEvent::PlayerJoined {
+            name: name.to_string(),
+            stream_id: format!("{}:1", name),
+        }
+    }
+
+    fn maze() -> ChallengeType {
+        ChallengeType {
+            name: "MonstrousMaze".to_string(),
+            input: json!({"grid": "I X", "endurance": 2}),
+        }
+    }
+
+    fn answer_events(player: &str, path: &str, passed: bool) -> Vec<GameEvent> {
+        vec![
+            GameEvent::ChallengeIssued {
+                player: player.to_string(),
+                challenge: maze(),
+                difficulty: None,
+            },
+            GameEvent::AnswerReceived {
+                player: player.to_string(),
+                answer: ChallengeAnswer {
+                    name: "MonstrousMaze".to_string(),
+                    output: json!({ "path": path }),
+                },
+                used_time: 0.5,
+                next_target: "bob".to_string(),
+            },
+            GameEvent::AnswerVerified {
+                player: player.to_string(),
+                passed,
+            },
+        ]
+    }
+
+    fn leader_board(alice: (i32, u32, f64)) -> GameEvent {
+        let mut player = PublicPlayer::new("alice".to_string(), "alice:1".to_string());
+        player.make_active("alice");
+        (player.score, player.steps, player.total_used_time) = alice;
+        GameEvent::GameEnded {
+            leader_board: vec![player],
+        }
+    }
+
+    fn replay_events(events: Vec<GameEvent>) -> Replay {
+        let log = events.into_iter().map(line).collect::<Vec<_>>().join("\n");
+        let mut replay = replay(log.as_bytes());
+        replay.finish();
+        replay
+    }
+
+    #[test]
+    fn test_consistent_log() {
+        let mut events = vec![joined("alice")];
+        events.extend(answer_events("alice", ">>", true));
+        events.extend(answer_events("alice", "<", false));
+        events.push(GameEvent::ScoreChanged {
+            player: "alice".to_string(),
+            score: -1,
+            reason: ScoreReason::BadResult,
+        });
+        events.push(GameEvent::ScoreChanged {
+            player: "alice".to_string(),
+            score: -2,
+            reason: ScoreReason::RoundExpired,
+        });
+        events.push(GameEvent::ChallengeMissed {
+            player: "alice".to_string(),
+            value: ChallengeValue::Timeout,
+        });
+        events.push(leader_board((-2, 1, 0.5)));
+        let replay = replay_events(events);
+        assert_eq!(replay.verified_answers, 2);
+        assert!(
+            replay.discrepancies.is_empty(),
+            "{:?}",
+            replay.discrepancies
+        );
+    }
+
+    #[test]
+    fn test_wrong_verification() {
+        let mut events = vec![joined("alice")];
+        events.extend(answer_events("alice", "<", true));
+        events.push(leader_board((0, 1, 0.5)));
+        let replay = replay_events(events);
+        assert_eq!(replay.discrepancies.len(), 2, "{:?}", replay.discrepancies);
+        assert!(replay.discrepancies[0].starts_with("line 4: answer of \"alice\""));
+        assert!(replay.discrepancies[1].starts_with("leaderboard:"));
+    }
+
+    #[test]
+    fn test_games_of_the_same_log() {
+        let mut events = vec![GameEvent::GameStarted { seed: 1 }, joined("alice")];
+        events.extend(answer_events("alice", ">>", true));
+        events.push(leader_board((0, 1, 0.5)));
+        events.extend([GameEvent::GameStarted { seed: 2 }, joined("alice")]);
+        events.extend(answer_events("alice", "<", false));
+        events.push(GameEvent::ScoreChanged {
+            player: "alice".to_string(),
+            score: -1,
+            reason: ScoreReason::BadResult,
+        });
+        events.push(leader_board((-1, 0, 0.0)));
+        let replay = replay_events(events);
+        assert!(
+            replay.discrepancies.is_empty(),
+            "{:?}",
+            replay.discrepancies
+        );
+        let seeds: Vec<_> = replay.games.iter().map(|game| game.seed).collect();
+        assert_eq!(seeds, vec![Some(1), Some(2)]);
+        assert!(replay.games.iter().all(|game| game.has_end_of_game));
+        assert_eq!(replay.games[1].players[0].score, -1);
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let log = format!(
+            "{}\nnot json\n\n{}",
+            line(joined("alice")),
+            line(leader_board((0, 0, 0.0)))
+        );
+        let mut replay = replay(log.as_bytes());
+        replay.finish();
+        assert_eq!(replay.discrepancies.len(), 1);
+        assert!(replay.discrepancies[0].starts_with("line 2: invalid event"));
+    }
+}
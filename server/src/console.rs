@@ -0,0 +1,99 @@
+use std::{io::BufRead, str::FromStr};
+
+use log::{info, warn};
+
+use crate::{exchanger::Exchanger, game::Game};
+
+const HELP: &str = "Commands: players, start, kick <name>, pause, resume, end, help";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Players,
+    Start,
+    Kick(String),
+    Pause,
+    Resume,
+    End,
+    Help,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let argument = words.next();
+        match (command, argument) {
+            ("players", None) => Ok(Command::Players),
+            ("start", None) => Ok(Command::Start),
+            ("kick", Some(name)) => Ok(Command::Kick(name.to_string())),
+            ("pause", None) => Ok(Command::Pause),
+            ("resume", None) => Ok(Command::Resume),
+            ("end", None) => Ok(Command::End),
+            ("help", None) => Ok(Command::Help),
+            _ => Err(format!("Unknown command {:?}", line.trim())),
+        }
+    }
+}
+
+/// Operator console reading one command per line, used to seal the lobby and drive the game
+pub struct Console {
+    exchanger: Exchanger,
+    game: Game,
+}
+
+impl Console {
+    pub fn new(exchanger: Exchanger, game: Game) -> Console {
+        Console { exchanger, game }
+    }
+
+    /// Runs commands until the input is closed or the game is over
+    pub fn run<R: BufRead>(&mut self, input: R) {
+        println!("{}", HELP);
+        for line in input.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    warn!("Cannot read console input : {}", err);
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.parse::<Command>() {
+                Ok(command) => self.execute(command),
+                Err(err) => println!("{}. {}", err, HELP),
+            }
+            if self.game.is_over() {
+                break;
+            }
+        }
+    }
+
+    fn execute(&mut self, command: Command) {
+        match command {
+            Command::Players => self.show_players(),
+            Command::Start => self.exchanger.start_game(),
+            Command::Kick(name) => {
+                if self.game.players.kick_player(&name) {
+                    info!("Player {:?} kicked", name);
+                } else {
+                    println!("No player named {:?}", name);
+                }
+            }
+            Command::Pause => self.exchanger.pause_game(),
+            Command::Resume => self.exchanger.resume_game(),
+            Command::End => self.exchanger.end_game(),
+            Command::Help => println!("{}", HELP),
+        }
+    }
+
+    fn show_players(&self) {
+        println!("Game is {:?}", self.game.get_state());
+        self.game.get_players().iter().for_each(|player| {
+            println!("{:?}", player);
+        });
+    }
+}
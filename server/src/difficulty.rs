@@ -0,0 +1,141 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::game::PlayerName;
+
+/// Solve times remembered per player and game type
+const HISTORY: usize = 5;
+/// Difficulty of the first challenge of a game type sent to a player
+pub const INITIAL_DIFFICULTY: f64 = 0.3;
+/// Difficulty change when the recent solve times are twice as long or as short as the target
+const GAIN: f64 = 0.05;
+/// Largest difficulty change after a single solve
+const MAX_STEP: f64 = 0.1;
+
+#[derive(Debug, Clone)]
+struct PlayerDifficulty {
+    difficulty: f64,
+    used_times: VecDeque<f64>,
+}
+
+impl Default for PlayerDifficulty {
+    fn default() -> Self {
+        PlayerDifficulty {
+            difficulty: INITIAL_DIFFICULTY,
+            used_times: VecDeque::with_capacity(HISTORY),
+        }
+    }
+}
+
+/// Adjusts the difficulty of each player and game type so that its solve times converge to a
+/// target time, a difficulty goes from 0 (easiest) to 1 (hardest)
+#[derive(Debug, Clone)]
+pub struct DifficultyController {
+    /// Solve time aimed at, in seconds
+    pub target_time: f64,
+    difficulties: Arc<Mutex<HashMap<(PlayerName, String), PlayerDifficulty>>>,
+}
+
+impl DifficultyController {
+    /// Aims at `target_ratio` of the challenge timeout
+    pub fn new(challenge_timeout: Duration, target_ratio: f64) -> DifficultyController {
+        DifficultyController {
+            target_time: challenge_timeout.as_secs_f64() * target_ratio,
+            difficulties: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Difficulty of the next challenge of this game type sent to the player
+    pub fn difficulty(&self, player: &str, game_type: &str) -> f64 {
+        self.difficulties
+            .lock()
+            .unwrap()
+            .get(&(player.to_string(), game_type.to_string()))
+            .map_or(INITIAL_DIFFICULTY, |entry| entry.difficulty)
+    }
+
+    /// Records a solve time in seconds and moves the difficulty towards the target: the step
+    /// grows with the log ratio between the mean of the recent solve times and the target time
+    pub fn record(&self, player: &str, game_type: &str, used_time: f64) {
+        let mut difficulties = self.difficulties.lock().unwrap();
+        let entry = difficulties
+            .entry((player.to_string(), game_type.to_string()))
+            .or_default();
+        if entry.used_times.len() == HISTORY {
+            entry.used_times.pop_front();
+        }
+        entry.used_times.push_back(used_time.max(f64::EPSILON));
+        let mean = entry.used_times.iter().sum::<f64>() / entry.used_times.len() as f64;
+        let step = (-GAIN * (mean / self.target_time).log2()).clamp(-MAX_STEP, MAX_STEP);
+        entry.difficulty = (entry.difficulty + step).clamp(0.0, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> DifficultyController {
+        DifficultyController::new(Duration::from_secs(2), 0.5)
+    }
+
+    #[test]
+    fn test_initial_difficulty() {
+        let controller = controller();
+        assert_eq!(controller.target_time, 1.0);
+        assert_eq!(
+            controller.difficulty("alice", "hash-cash"),
+            INITIAL_DIFFICULTY
+        );
+    }
+
+    #[test]
+    fn test_record_moves_towards_target() {
+        let controller = controller();
+        controller.record("alice", "hash-cash", 0.1);
+        controller.record("bob", "hash-cash", 1.9);
+        controller.record("carol", "hash-cash", 1.0);
+        assert!(controller.difficulty("alice", "hash-cash") > INITIAL_DIFFICULTY);
+        assert!(controller.difficulty("bob", "hash-cash") < INITIAL_DIFFICULTY);
+        assert_eq!(
+            controller.difficulty("carol", "hash-cash"),
+            INITIAL_DIFFICULTY
+        );
+        assert_eq!(
+            controller.difficulty("alice", "bloxorz"),
+            INITIAL_DIFFICULTY
+        );
+    }
+
+    #[test]
+    fn test_record_is_bounded() {
+        let controller = controller();
+        controller.record("alice", "hash-cash", 0.0);
+        assert_eq!(
+            controller.difficulty("alice", "hash-cash"),
+            INITIAL_DIFFICULTY + MAX_STEP
+        );
+        for _ in 0..20 {
+            controller.record("alice", "hash-cash", 0.0);
+            controller.record("bob", "hash-cash", 2.0);
+        }
+        assert_eq!(controller.difficulty("alice", "hash-cash"), 1.0);
+        assert_eq!(controller.difficulty("bob", "hash-cash"), 0.0);
+    }
+
+    #[test]
+    fn test_converges_to_target_time() {
+        let controller = controller();
+        // Solve time doubling every 0.1 of difficulty, 1 second at a difficulty of 0.6
+        let solve_time = |difficulty: f64| 2f64.powf((difficulty - 0.6) * 10.0);
+        for _ in 0..50 {
+            let difficulty = controller.difficulty("alice", "hash-cash");
+            controller.record("alice", "hash-cash", solve_time(difficulty));
+        }
+        let difficulty = controller.difficulty("alice", "hash-cash");
+        assert!((difficulty - 0.6).abs() < 0.05, "{}", difficulty);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use log::error;
+use shared::event::{EventRecord, GameEvent};
+
+/// JSON-lines log of the game events, appended to a file. A disabled log drops the events.
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    writer: Option<Arc<Mutex<LineWriter<File>>>>,
+    start: Instant,
+}
+
+impl EventLog {
+    pub fn disabled() -> EventLog {
+        EventLog {
+            writer: None,
+            start: Instant::now(),
+        }
+    }
+
+    /// Appends to the file at `path`, creating it if needed
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<EventLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(EventLog {
+            writer: Some(Arc::new(Mutex::new(LineWriter::new(file)))),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&self, event: GameEvent) {
+        let writer = match &self.writer {
+            Some(writer) => writer,
+            None => return,
+        };
+        let record = EventRecord {
+            time: self.start.elapsed().as_secs_f64(),
+            event,
+        };
+        let line = serde_json::to_string(&record).expect("Could not serialize event");
+        if let Err(err) = writeln!(writer.lock().unwrap(), "{}", line) {
+            error!("Cannot write to the event log: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_record_appends_lines() {
+        let path = std::env::temp_dir().join(format!("event-log-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        for round in 0..2 {
+            let log = EventLog::open(&path).unwrap();
+            log.record(GameEvent::RoundStarted {
+                round,
+                game_type: "hash-cash".to_string(),
+            });
+        }
+        EventLog::disabled().record(GameEvent::RoundStarted {
+            round: 2,
+            game_type: "hash-cash".to_string(),
+        });
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let rounds: Vec<usize> = content
+            .lines()
+            .map(
+                |line| match serde_json::from_str::<EventRecord>(line).unwrap().event {
+                    GameEvent::RoundStarted { round, .. } => round,
+                    other => panic!("Unexpected event {:?}", other),
+                },
+            )
+            .collect();
+        assert_eq!(rounds, vec![0, 1]);
+    }
+}
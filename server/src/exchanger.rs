@@ -1,23 +1,25 @@
 use std::{
-    io::Read,
     net::{Shutdown, TcpStream},
     sync::mpsc::Sender,
+    thread,
+    time::Duration,
 };
 
 use log::{debug, error, info, trace, warn};
 
-use hashcash::dto::{MD5HashCash, MD5HashCashInput};
-use monstrous_maze::{
-    challenge_generator::generate_monstrous_maze_challenge, models::MonstrousMaze,
-};
-use recover_secret::{challenge_generator::generate_challenge, models::RecoverSecret};
 use shared::{
-    challenge::{ChallengeType, ChallengeValue, GameType},
+    challenge::{ChallengeType, ChallengeValue, ReportedChallengeResult},
+    codec::{FrameError, FrameReader},
+    event::{GameEvent, ScoreReason},
     message::{Message, MessageType, PublicLeaderBoard},
+    registry::{registry, GeneratorContext},
 };
 
 use crate::{game::Game, message_handler::MessageHandler};
 
+/// Longest pause of the challenge watchdog between two checks
+const WATCHDOG_MAX_TICK: Duration = Duration::from_millis(50);
+
 pub struct Exchanger {
     message_handler: MessageHandler,
     game: Game,
@@ -39,19 +41,15 @@ impl Exchanger {
             .expect("Cannot retrieve client address")
             .to_string();
         info!("peer address={:?}", &client_id);
+        let mut reader = FrameReader::new(&stream);
         loop {
-            let parsed_message = self.parse_message_from_tcp_stream(&stream);
-            let response = self.message_handler.handle_message(
-                parsed_message,
-                client_id.clone(),
-                self.game.get_challenge(),
-            );
-
-            if matches!(response.message, Message::EndOfCommunication) {
+            let parsed_message = match self.read_message(&mut reader) {
+                Some(message) => message,
+                None => continue,
+            };
+            if !self.handle_client_message(parsed_message, &client_id) {
                 break;
             }
-            self.check_start_round(response.clone());
-            self.check_end_challenge(response, client_id.clone());
         }
 
         let shutdown_result = stream.shutdown(Shutdown::Both);
@@ -60,63 +58,216 @@ impl Exchanger {
         }
     }
 
-    fn check_end_challenge(&mut self, response: MessageType, client_id: String) {
-        let mut is_end_of_round = false;
+    /// Handles a message of the client, returns `false` once the communication is over
+    fn handle_client_message(&mut self, message: Message, client_id: &str) -> bool {
+        if matches!(message, Message::StartGame {}) {
+            warn!(
+                "Ignoring StartGame of {:?}, only the operator starts the game",
+                client_id
+            );
+            return true;
+        }
+        if matches!(message, Message::ChallengeResult { .. })
+            && !self.game.take_actual_player_by_stream_id(client_id)
+        {
+            warn!(
+                "Ignoring challenge result of {:?}, no challenge is pending for it",
+                client_id
+            );
+            return true;
+        }
+        let response = self.message_handler.handle_message(
+            message,
+            client_id.to_string(),
+            self.game.get_challenge(),
+        );
+
+        if matches!(response.message, Message::EndOfCommunication) {
+            self.check_unreachable(client_id.to_string());
+            return false;
+        }
         if matches!(response.message, Message::RoundSummary { .. }) {
-            let mut current_round = self.game.current_round.lock().unwrap();
-            if let Some(current_round) = &mut *current_round {
-                if current_round.start.elapsed() > current_round.duration {
-                    let actual_player = current_round
-                        .actual_player
-                        .clone()
-                        .expect("No actual player when challenge end");
-                    self.game.update_score(actual_player.as_str());
-
-                    self.show_leaderboard();
-                    is_end_of_round = true;
-                }
+            self.check_end_challenge(client_id.to_string());
+        } else {
+            self.check_start_round(response);
+        }
+        true
+    }
+
+    /// Ends the round when the targeted player does not answer its challenge in time
+    pub fn watch_challenge_timeout(&mut self) {
+        let tick = WATCHDOG_MAX_TICK.min(self.game.challenge_timeout / 10);
+        while !self.game.is_over() {
+            thread::sleep(tick);
+            if let Some(name) = self.game.take_timed_out_player() {
+                info!("Player {:?} did not answer in time", name);
+                self.game
+                    .record_used_time(&name, self.game.challenge_timeout.as_secs_f64());
+                self.exclude_player(name, ChallengeValue::Timeout);
             }
-            drop(current_round);
-            if is_end_of_round {
-                self.game.push_current_round();
-                let message = self.start_round();
-                self.tx
-                    .send(message)
-                    .expect("Cannot send message, no receiver.");
-                return;
+        }
+    }
+
+    /// Seals the lobby and starts the first round, the lobby stays open until a player subscribed
+    pub fn start_game(&mut self) {
+        let has_active_player = self.game.get_players().iter().any(|p| p.is_active);
+        if !self.game.is_lobby_sealed() && !has_active_player {
+            warn!("No player subscribed yet, the lobby stays open");
+            return;
+        }
+        if !self.game.seal_lobby() {
+            warn!("The game is already started");
+            return;
+        }
+        info!("Lobby sealed, start of the game");
+        self.check_start_round(MessageType::boardcast(Message::PublicLeaderBoard(
+            self.game.get_players(),
+        )));
+    }
+
+    pub fn pause_game(&mut self) {
+        if self.game.pause() {
+            info!("Game paused, no round will start until it is resumed");
+        } else {
+            warn!("Only a running game can be paused");
+        }
+    }
+
+    pub fn resume_game(&mut self) {
+        if !self.game.resume() {
+            warn!("Only a paused game can be resumed");
+            return;
+        }
+        info!("Game resumed");
+        if !self.game.is_round_running() {
+            self.next_round();
+        }
+    }
+
+    /// Ends the game right away, the current round is closed with its partial chain
+    pub fn end_game(&mut self) {
+        if self.game.is_over() {
+            warn!("The game is already over");
+            return;
+        }
+        self.game.end();
+        if self.game.is_round_running() {
+            self.end_round();
+        } else {
+            self.send_end_of_game();
+        }
+    }
+
+    fn check_unreachable(&mut self, client_id: String) {
+        let player = self.game.players.get_player_by_stream_id(&client_id);
+        let is_actual_player = self.game.take_actual_player_by_stream_id(&client_id);
+        self.game.players.disable_player(client_id);
+        if let Some(player) = player {
+            if is_actual_player {
+                info!("Player {:?} is unreachable", player.name);
+                self.exclude_player(player.name, ChallengeValue::Unreachable);
             }
+        }
+    }
 
-            trace!("End of challenge");
-            let challenge = self.get_new_challenge();
-            self.game.set_challenge(challenge.clone());
-            trace!("chain: {:?}", self.game.chain);
-            if let Some(challenge_result) = self.game.get_last_chain_result() {
-                debug!("{:?}", challenge_result);
-                match &challenge_result.value {
-                    ChallengeValue::Unreachable | ChallengeValue::Timeout => {
-                        self.game.players.disable_player(client_id)
-                    }
-                    ChallengeValue::BadResult {
-                        used_time: _,
-                        next_target,
+    fn exclude_player(&mut self, name: String, value: ChallengeValue) {
+        if let Some(player) = self.game.get_player_by_name(&name) {
+            self.game.players.disable_player(player.stream_id);
+        }
+        self.game.events.record(GameEvent::ChallengeMissed {
+            player: name.clone(),
+            value: value.clone(),
+        });
+        self.game
+            .push_reported_challenge_result(ReportedChallengeResult { name, value });
+        self.end_round();
+    }
+
+    fn check_end_challenge(&mut self, client_id: String) {
+        if !self.game.is_round_running() {
+            debug!("Round already closed, ignoring end of challenge");
+            return;
+        }
+        trace!("End of challenge");
+        trace!("chain: {:?}", self.game.chain);
+        if let Some(challenge_result) = self.game.get_last_chain_result() {
+            debug!("{:?}", challenge_result);
+            match &challenge_result.value {
+                // Pushed by `exclude_player`, which already ended the round
+                ChallengeValue::Unreachable | ChallengeValue::Timeout => {}
+                ChallengeValue::BadResult { .. } => self.end_round(),
+                ChallengeValue::Ok { .. } if self.game.is_round_expired() => {
+                    if let Some(player) = self.game.players.get_player_by_stream_id(&client_id) {
+                        self.game
+                            .update_score(player.name.as_str(), ScoreReason::RoundExpired);
                     }
-                    | ChallengeValue::Ok {
-                        used_time: _,
-                        next_target,
-                    } => {
-                        let message = Message::Challenge(challenge);
-                        if let Some(player) = self.game.get_player_by_name(next_target) {
+                    self.end_round();
+                }
+                ChallengeValue::Ok {
+                    used_time: _,
+                    next_target,
+                } => {
+                    let player = self
+                        .game
+                        .get_player_by_name(next_target)
+                        .filter(|player| player.is_active)
+                        .or_else(|| self.game.pick_random_active_player());
+                    match player {
+                        Some(player) => {
+                            let challenge = self.get_new_challenge(&player.name);
+                            self.game.set_challenge(challenge.clone());
+                            let message = Message::Challenge(challenge);
                             self.game.set_active_player(player.name.clone());
                             self.tx
                                 .send(MessageType::unicast(message, player.stream_id))
                                 .expect("Cannot send message, no receiver.");
                         }
+                        None => self.end_round(),
                     }
                 }
             }
         }
     }
 
+    fn end_round(&mut self) {
+        let challenge = self
+            .game
+            .get_challenge()
+            .map(|challenge| challenge.name)
+            .unwrap_or_default();
+        let chain = self.game.take_chain();
+        self.game.record_round_end(&challenge, &chain);
+        let summary = Message::RoundSummary { challenge, chain };
+        self.tx
+            .send(MessageType::boardcast(summary))
+            .expect("Cannot send message, no receiver.");
+        self.game.push_current_round();
+        self.show_leaderboard();
+        self.next_round();
+    }
+
+    fn next_round(&mut self) {
+        if self.game.is_over() {
+            self.send_end_of_game();
+            return;
+        }
+        self.check_start_round(MessageType::boardcast(Message::PublicLeaderBoard(
+            self.game.get_players(),
+        )));
+    }
+
+    fn send_end_of_game(&mut self) {
+        info!("End of game");
+        self.game.events.record(GameEvent::GameEnded {
+            leader_board: self.game.get_players(),
+        });
+        self.tx
+            .send(MessageType::boardcast(Message::EndOfGame {
+                leader_board: self.game.get_players(),
+            }))
+            .expect("Cannot send message, no receiver.");
+    }
+
     fn show_leaderboard(&self) {
         print!("\x1B[2J\x1B[1;1H");
         println!("Leaderboard :");
@@ -135,60 +286,114 @@ impl Exchanger {
         self.tx
             .send(response)
             .expect("Cannot send message, no receiver");
-        if is_start_round {
-            let challenge_message = self.start_round();
-            self.tx
-                .send(challenge_message)
-                .expect("Cannot send message, no receiver");
+        if is_start_round && !self.game.can_start_round() {
+            info!("Game is {:?}, no new round started", self.game.get_state());
+        } else if is_start_round {
+            match self.start_round() {
+                Some(challenge_message) => self
+                    .tx
+                    .send(challenge_message)
+                    .expect("Cannot send message, no receiver"),
+                None => error!("No active player found, cannot start a new round"),
+            }
         }
     }
 
-    fn parse_message_from_tcp_stream(&self, mut stream: &TcpStream) -> Message {
-        let mut message_size = [0; 4];
-        let _size_error = stream.read(&mut message_size);
-        let decimal_size = u32::from_be_bytes(message_size);
-
-        let mut bytes_of_message = vec![0; decimal_size as usize];
-        let _size_read = stream.read_exact(&mut bytes_of_message);
-        let message = String::from_utf8_lossy(&bytes_of_message);
-        let message = serde_json::from_str(&message);
-        match message {
-            Ok(m) => m,
+    /// Returns `None` when the frame was skipped and the stream is still usable
+    fn read_message(&self, reader: &mut FrameReader<&TcpStream>) -> Option<Message> {
+        match reader.read_message() {
+            Ok(message) => Some(message),
+            Err(err @ (FrameError::InvalidUtf8(_) | FrameError::InvalidJson(_))) => {
+                warn!("Cannot parse message : {}", err);
+                None
+            }
+            Err(FrameError::EndOfStream) => {
+                debug!("Client closed the connection");
+                Some(Message::EndOfCommunication)
+            }
             Err(err) => {
-                warn!("Cannot parse message : {:?}", err);
-                Message::EndOfCommunication
+                warn!("Cannot read message : {}", err);
+                Some(Message::EndOfCommunication)
             }
         }
     }
 
-    fn start_round(&self) -> MessageType {
+    fn start_round(&self) -> Option<MessageType> {
         trace!("start round");
-        let challenge = self.get_new_challenge();
+        let player = self.game.pick_random_active_player()?;
+        let game_type = self.game.next_game_type();
+        debug!("Game type of the round: {}", game_type);
+        self.game.start_round(game_type);
+        let challenge = self.get_new_challenge(&player.name);
         self.game.set_challenge(challenge.clone());
 
         let message = Message::Challenge(challenge);
-        let player = self.game.players.pick_random_active_player();
-        if player.is_none() {
-            error!("No player found");
-            panic!("No player found");
-        }
-        let player = player.unwrap();
-        self.game.start_round();
         self.game.set_active_player(player.name.clone());
 
-        MessageType::unicast(message, player.stream_id)
+        Some(MessageType::unicast(message, player.stream_id))
     }
 
-    fn get_new_challenge(&self) -> ChallengeType {
-        match self.game.game_type {
-            GameType::HashCash => ChallengeType::MD5HashCash(MD5HashCash(MD5HashCashInput::new())),
-            GameType::RecoverSecret => {
-                ChallengeType::RecoverSecret(RecoverSecret(generate_challenge()))
-            }
+    /// New challenge of the game type of the running round, sent to the player
+    fn get_new_challenge(&self, player: &str) -> ChallengeType {
+        let game_type = self
+            .game
+            .get_round_game_type()
+            .unwrap_or_else(|| self.game.next_game_type());
+        let difficulty = self.game.issue_difficulty(player);
+        let context = GeneratorContext::new(self.game.complexity).with_difficulty(difficulty);
+        let challenge = registry()
+            .generate(&game_type, &context, &mut *self.game.rng.lock().unwrap())
+            .expect("The game types are checked when parsing the arguments");
+        self.game.events.record(GameEvent::ChallengeIssued {
+            player: player.to_string(),
+            challenge: challenge.clone(),
+            difficulty,
+        });
+        challenge
+    }
+}
 
-            GameType::MonstrousMaze => {
-                ChallengeType::MonstrousMaze(MonstrousMaze(generate_monstrous_maze_challenge()))
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use recover_secret::challenge_generator::DEFAULT_COMPLEXITY;
+
+    use crate::event_log::EventLog;
+    use crate::game::GameState;
+    use crate::rotation::{ChallengeRotation, GameMix, RotationPolicy};
+
+    use super::*;
+
+    fn new_game() -> Game {
+        Game::new(
+            ChallengeRotation::new(GameMix::default(), RotationPolicy::RoundRobin),
+            Duration::from_secs(3),
+            Duration::from_secs(2),
+            10,
+            DEFAULT_COMPLEXITY,
+            None,
+            EventLog::disabled(),
+        )
+    }
+
+    #[test]
+    fn test_client_cannot_start_the_game() {
+        let game = new_game();
+        let (tx, rx) = mpsc::channel();
+        let mut exchanger = Exchanger::new(MessageHandler::new(game.clone()), game.clone(), tx);
+        assert!(exchanger.handle_client_message(Message::StartGame {}, "127.0.0.1:1234"));
+        assert_eq!(game.get_state(), GameState::Lobby);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_start_game_without_player() {
+        let game = new_game();
+        let (tx, rx) = mpsc::channel();
+        let mut exchanger = Exchanger::new(MessageHandler::new(game.clone()), game.clone(), tx);
+        exchanger.start_game();
+        assert_eq!(game.get_state(), GameState::Lobby);
+        assert!(rx.try_recv().is_err());
     }
 }
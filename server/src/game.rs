@@ -5,12 +5,17 @@ use std::{
 };
 
 use log::{debug, error, trace};
+use rand::{rngs::StdRng, SeedableRng};
 use shared::{
-    challenge::{ChallengeType, GameType, ReportedChallengeResult},
+    challenge::{ChallengeType, ReportedChallengeResult},
+    event::{GameEvent, ScoreReason},
     public_player::PublicPlayer,
 };
 
+use crate::difficulty::DifficultyController;
+use crate::event_log::EventLog;
 use crate::player::{Player, PlayerList};
+use crate::rotation::ChallengeRotation;
 
 pub type PlayerName = String;
 
@@ -21,48 +26,101 @@ pub struct Round {
     pub last_resolved: Instant,
     pub duration: Duration,
     pub actual_player: Option<PlayerName>,
+    /// Game type of every challenge of the round
+    pub game_type: String,
+    /// Difficulty chosen for each challenge of the round with adaptive difficulty, in order
+    pub difficulties: Vec<(PlayerName, f64)>,
 }
 
 impl Round {
-    pub fn new(duration: Duration) -> Round {
+    pub fn new(duration: Duration, game_type: String) -> Round {
         Round {
             solvers: HashSet::new(),
             start: Instant::now(),
             last_resolved: Instant::now(),
             duration,
             actual_player: None,
+            game_type,
+            difficulties: Vec::new(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    /// Players can still join, no round has been played
+    Lobby,
+    Running,
+    /// The current round ends normally but no new round is started
+    Paused,
+    Ended,
+}
+
 #[derive(Debug, Clone)]
 pub struct Game {
     pub players: PlayerList,
     pub challenge: Arc<Mutex<Option<ChallengeType>>>,
-    pub game_type: GameType,
+    /// Game types played and how they follow each other from round to round
+    pub rotation: ChallengeRotation,
     pub chain: Arc<Mutex<Vec<ReportedChallengeResult>>>,
     pub rounds: Arc<Mutex<Vec<Round>>>,
     pub current_round: Arc<Mutex<Option<Round>>>,
     pub round_duration: Duration,
+    pub challenge_timeout: Duration,
+    pub max_rounds: usize,
+    /// Complexity of the generated RecoverSecret challenges
+    pub complexity: u8,
+    /// Adapts the challenges to the solve times of each player, `None` keeps them random
+    pub difficulty: Option<DifficultyController>,
+    pub events: EventLog,
+    /// Seed of the random generator of each round, a game replayed with the same seed and
+    /// players gets the same challenges
+    pub seed: u64,
+    /// Draws the game types, the targeted players and the challenges of the running round
+    pub rng: Arc<Mutex<StdRng>>,
+    pub state: Arc<Mutex<GameState>>,
 }
 
 impl Game {
-    pub fn new(game_type: GameType, round_duration: Duration) -> Game {
+    pub fn new(
+        rotation: ChallengeRotation,
+        round_duration: Duration,
+        challenge_timeout: Duration,
+        max_rounds: usize,
+        complexity: u8,
+        difficulty: Option<DifficultyController>,
+        events: EventLog,
+    ) -> Game {
         let players = PlayerList::new();
         let challenge = Arc::new(Mutex::new(None));
         let chain = Arc::new(Mutex::new(Vec::new()));
         let rounds = Arc::new(Mutex::new(Vec::new()));
         let current_round = Arc::new(Mutex::new(None));
+        let seed = rand::random();
         Game {
             players,
             challenge,
-            game_type,
+            rotation,
             chain,
             rounds,
             current_round,
             round_duration,
+            challenge_timeout,
+            max_rounds,
+            complexity,
+            difficulty,
+            events,
+            seed,
+            rng: Arc::new(Mutex::new(round_rng(seed, 0))),
+            state: Arc::new(Mutex::new(GameState::Lobby)),
         }
     }
+    /// Same game with a fixed seed instead of a random one
+    pub fn with_seed(self, seed: u64) -> Game {
+        *self.rng.lock().unwrap() = round_rng(seed, 0);
+        Game { seed, ..self }
+    }
+
     pub fn add_player(&mut self, player: Player) {
         self.players.add_player(player);
     }
@@ -71,6 +129,49 @@ impl Game {
         self.players.get_players()
     }
 
+    /// Game type of the running round
+    pub fn get_round_game_type(&self) -> Option<String> {
+        self.current_round
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|round| round.game_type.clone())
+    }
+
+    /// Game type of the round about to start
+    pub fn next_game_type(&self) -> String {
+        let played = self.rounds.lock().unwrap().len();
+        self.rotation
+            .game_type_of_round(played, &mut *self.rng.lock().unwrap())
+    }
+
+    pub fn pick_random_active_player(&self) -> Option<PublicPlayer> {
+        self.players
+            .pick_random_active_player(&mut *self.rng.lock().unwrap())
+    }
+
+    /// Difficulty of the next challenge of the round sent to the player, recorded in the round
+    pub fn issue_difficulty(&self, player: &str) -> Option<f64> {
+        let controller = self.difficulty.as_ref()?;
+        let mut round = self.current_round.lock().unwrap();
+        let round = round.as_mut()?;
+        let difficulty = controller.difficulty(player, &round.game_type);
+        debug!(
+            "Difficulty {:.2} of {} for {:?}",
+            difficulty, round.game_type, player
+        );
+        round.difficulties.push((player.to_string(), difficulty));
+        Some(difficulty)
+    }
+
+    /// Feeds the difficulty controller with the time the player took on the round challenge
+    pub fn record_used_time(&self, player: &str, used_time: f64) {
+        if let (Some(controller), Some(game_type)) = (&self.difficulty, self.get_round_game_type())
+        {
+            controller.record(player, &game_type, used_time);
+        }
+    }
+
     pub fn get_challenge(&self) -> Option<ChallengeType> {
         self.challenge.lock().unwrap().clone()
     }
@@ -84,34 +185,82 @@ impl Game {
         debug!("set_active_player lock: {:?}", round);
         if let Some(round) = &mut *round {
             round.actual_player = Some(name);
+            round.last_resolved = Instant::now();
         }
         drop(round);
     }
 
-    pub fn update_winner(&mut self, client_id: &str) {
-        let player = self
-            .players
-            .get_and_remove_player_by_stream_id(client_id.to_string());
-        if let Some(mut player) = player {
+    /// Closes the pending challenge if it was sent to the player of this stream
+    pub fn take_actual_player_by_stream_id(&self, client_id: &str) -> bool {
+        let player = match self.players.get_player_by_stream_id(client_id) {
+            Some(player) => player,
+            None => return false,
+        };
+        let mut round = self.current_round.lock().unwrap();
+        match &mut *round {
+            Some(round) if round.actual_player.as_ref() == Some(&player.name) => {
+                round.actual_player = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Closes the pending challenge if its player did not answer before the challenge timeout
+    pub fn take_timed_out_player(&self) -> Option<PlayerName> {
+        let mut round = self.current_round.lock().unwrap();
+        match &mut *round {
+            Some(round) if round.last_resolved.elapsed() > self.challenge_timeout => {
+                round.actual_player.take()
+            }
+            _ => None,
+        }
+    }
+
+    /// Time elapsed since the pending challenge was sent
+    pub fn get_challenge_elapsed(&self) -> Duration {
+        match &*self.current_round.lock().unwrap() {
+            Some(round) => round.last_resolved.elapsed(),
+            None => Duration::ZERO,
+        }
+    }
+
+    pub fn is_round_expired(&self) -> bool {
+        match &*self.current_round.lock().unwrap() {
+            Some(round) => round.start.elapsed() > round.duration,
+            None => false,
+        }
+    }
+
+    pub fn update_winner(&mut self, client_id: &str, used_time: f64) {
+        let mut players = self.players.players.lock().unwrap();
+        let player = players
+            .iter_mut()
+            .find(|p| p.info_public.stream_id == client_id);
+        if let Some(player) = player {
             let mut current_round = self.current_round.lock().unwrap();
             if let Some(current_round) = &mut *current_round {
                 current_round
                     .solvers
                     .insert(player.info_public.name.clone());
                 player.info_public.steps += 1;
-                player.info_public.total_used_time +=
-                    current_round.last_resolved.elapsed().as_micros() as f64;
+                player.info_public.total_used_time += used_time;
             } else {
                 error!("No current round to update winner");
             }
-
-            self.players.add_player(player);
-            trace!("players: {:?}", self.players);
+            trace!("player: {:?}", player);
         }
     }
 
-    pub fn update_score(&self, name: &str) {
+    pub fn update_score(&self, name: &str, reason: ScoreReason) {
         self.players.decrease_score(name);
+        if let Some(player) = self.players.get_player_by_name(name) {
+            self.events.record(GameEvent::ScoreChanged {
+                player: player.name,
+                score: player.score,
+                reason,
+            });
+        }
     }
 
     pub fn get_chain(&self) -> Vec<ReportedChallengeResult> {
@@ -122,6 +271,10 @@ impl Game {
         self.chain.lock().unwrap().push(result);
     }
 
+    pub fn take_chain(&self) -> Vec<ReportedChallengeResult> {
+        std::mem::take(&mut *self.chain.lock().unwrap())
+    }
+
     pub fn get_last_chain_result(&self) -> Option<ReportedChallengeResult> {
         self.chain.lock().unwrap().last().cloned()
     }
@@ -130,17 +283,94 @@ impl Game {
         self.players.get_player_by_name(name)
     }
 
-    pub fn start_round(&self) {
-        let current_round = Round::new(self.round_duration);
+    pub fn get_state(&self) -> GameState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Closes the lobby, returns `false` if the game was already started
+    pub fn seal_lobby(&self) -> bool {
+        self.switch_state(GameState::Lobby, GameState::Running)
+    }
+
+    pub fn is_lobby_sealed(&self) -> bool {
+        self.get_state() != GameState::Lobby
+    }
+
+    pub fn pause(&self) -> bool {
+        self.switch_state(GameState::Running, GameState::Paused)
+    }
+
+    pub fn resume(&self) -> bool {
+        self.switch_state(GameState::Paused, GameState::Running)
+    }
+
+    pub fn end(&self) {
+        *self.state.lock().unwrap() = GameState::Ended;
+    }
+
+    fn switch_state(&self, from: GameState, to: GameState) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if *state != from {
+            return false;
+        }
+        *state = to;
+        true
+    }
+
+    /// The game is over once `max_rounds` rounds have been played or when the operator ends it
+    pub fn is_over(&self) -> bool {
+        self.get_state() == GameState::Ended || self.rounds.lock().unwrap().len() >= self.max_rounds
+    }
+
+    pub fn can_start_round(&self) -> bool {
+        self.get_state() == GameState::Running && !self.is_over() && !self.is_round_running()
+    }
+
+    pub fn is_round_running(&self) -> bool {
+        self.current_round.lock().unwrap().is_some()
+    }
+
+    pub fn start_round(&self, game_type: String) {
+        self.events.record(GameEvent::RoundStarted {
+            round: self.rounds.lock().unwrap().len(),
+            game_type: game_type.clone(),
+        });
+        let current_round = Round::new(self.round_duration, game_type);
         self.current_round.lock().unwrap().replace(current_round);
     }
 
+    /// Records the end of the running round with its chain of results
+    pub fn record_round_end(&self, challenge: &str, chain: &[ReportedChallengeResult]) {
+        let difficulties = match &*self.current_round.lock().unwrap() {
+            Some(round) => round.difficulties.clone(),
+            None => Vec::new(),
+        };
+        self.events.record(GameEvent::RoundEnded {
+            round: self.rounds.lock().unwrap().len(),
+            challenge: challenge.to_string(),
+            chain: chain.to_vec(),
+            difficulties,
+        });
+    }
+
     pub fn push_current_round(&mut self) {
         let mut rounds = self.rounds.lock().unwrap();
-        let current_round = self.current_round.lock().unwrap().clone();
-        rounds.push(current_round.expect("No current round to push"));
+        match self.current_round.lock().unwrap().take() {
+            Some(current_round) => rounds.push(current_round),
+            None => error!("No current round to push"),
+        }
+        // A round draws as many values as it has challenges, reseeding keeps the next rounds
+        // independent of how long this one lasted
+        *self.rng.lock().unwrap() = round_rng(self.seed, rounds.len());
     }
 }
+
+/// Random generator of the round `round` of a game, the round is spread over the bits of the
+/// seed so that games of close seeds do not share rounds
+fn round_rng(seed: u64, round: usize) -> StdRng {
+    StdRng::seed_from_u64(seed ^ (round as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
 // match challenge_type.as_str() {
 //    hashcash => {
 //     let challenge = Arc::new(Mutex::new(Some(ChallengeType::MD5HashCash(()))));
@@ -148,3 +378,51 @@ impl Game {
 //   }
 //   _ => panic!("Challenge Not implemented")
 // }
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use crate::rotation::RotationPolicy;
+
+    use super::*;
+
+    fn seeded_game(seed: u64) -> Game {
+        Game::new(
+            ChallengeRotation::new(
+                "hash-cash,monstrous-maze,bloxorz".parse().unwrap(),
+                RotationPolicy::Random,
+            ),
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            10,
+            1,
+            None,
+            EventLog::disabled(),
+        )
+        .with_seed(seed)
+    }
+
+    /// Game types of 8 rounds, each round drawing `draws(round)` values like its challenges
+    fn game_types(seed: u64, draws: fn(usize) -> usize) -> Vec<String> {
+        let mut game = seeded_game(seed);
+        (0..8)
+            .map(|round| {
+                let game_type = game.next_game_type();
+                game.start_round(game_type.clone());
+                for _ in 0..draws(round) {
+                    game.rng.lock().unwrap().gen::<u64>();
+                }
+                game.push_current_round();
+                game_type
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded_rounds_are_reproducible() {
+        let game_types_of_seed = game_types(25, |_| 3);
+        assert_eq!(game_types(25, |round| round * 7), game_types_of_seed);
+        assert_ne!(game_types(26, |_| 3), game_types_of_seed);
+    }
+}
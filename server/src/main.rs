@@ -2,15 +2,23 @@ use std::time::Duration;
 
 use args::ServerArgs;
 use clap::Parser;
+use difficulty::DifficultyController;
+use event_log::EventLog;
 use game::Game;
+use log::info;
+use rotation::{ChallengeRotation, RotationPolicy};
 use server::{create_listener, Server};
-use shared::challenge::GameType;
+use shared::event::GameEvent;
 
 mod args;
+mod console;
+mod difficulty;
+mod event_log;
 mod exchanger;
 mod game;
 mod message_handler;
 mod player;
+mod rotation;
 mod server;
 mod utils;
 
@@ -19,10 +27,31 @@ fn main() {
     std::env::set_var("RUST_LOG", args.log_level);
     pretty_env_logger::init();
     let listener = create_listener(format!("{}:{}", args.ip, args.port));
+    let challenge_timeout = Duration::from_millis(args.challenge_timeout);
+    let difficulty = args
+        .adaptive_difficulty
+        .then(|| DifficultyController::new(challenge_timeout, args.target_ratio));
+    let events = match &args.event_log {
+        Some(path) => EventLog::open(path)
+            .unwrap_or_else(|err| panic!("Cannot open the event log {:?}: {}", path, err)),
+        None => EventLog::disabled(),
+    };
     let game = Game::new(
-        GameType::from(args.game_type.as_str()),
+        ChallengeRotation::new(args.game_type, RotationPolicy::from(args.rotation.as_str())),
         Duration::from_secs(args.round_duration),
+        challenge_timeout,
+        args.rounds,
+        args.complexity,
+        difficulty,
+        events,
     );
+    let game = match args.seed {
+        Some(seed) => game.with_seed(seed),
+        None => game,
+    };
+    info!("Seed of the game: {}", game.seed);
+    game.events
+        .record(GameEvent::GameStarted { seed: game.seed });
     let mut server: Server = Server::new(listener, game);
     server.listen();
 }
@@ -1,10 +1,9 @@
-use log::{debug, error, info, trace};
-use shared::challenge::{
-    get_name_of_challenge_type, Challenge, ChallengeAnswer, ChallengeType, ChallengeValue,
-    ReportedChallengeResult,
-};
+use log::{debug, error, info, trace, warn};
+use shared::challenge::{ChallengeAnswer, ChallengeType, ChallengeValue, ReportedChallengeResult};
+use shared::event::{GameEvent, ScoreReason};
 use shared::message::{Message, MessageType};
-use shared::subscribe::{SubscribeError, SubscribeResult};
+use shared::registry::registry;
+use shared::subscribe::SubscribeResult;
 
 use crate::game::Game;
 #[derive(Debug)]
@@ -27,7 +26,9 @@ impl MessageHandler {
         match message {
             Message::Hello => self.handle_hello(client_id),
             Message::Subscribe { name } => self.handle_subscription(name, client_id),
-            Message::StartGame {} => self.handle_start_game(),
+            Message::StartGame {} => {
+                unreachable!("The exchanger drops StartGame, only the operator starts the game")
+            }
             Message::ChallengeResult {
                 answer,
                 next_target,
@@ -38,15 +39,23 @@ impl MessageHandler {
     }
 
     fn handle_subscription(&mut self, name: String, client_id: String) -> MessageType {
-        let answer = if self.game.players.has_player_with_name(&name) {
-            Message::SubscribeResult(SubscribeResult::Err(SubscribeError::AlreadyRegistered))
-        } else {
-            Message::SubscribeResult(SubscribeResult::Ok)
+        let result = match self.game.players.subscribe_player(&client_id, &name) {
+            Ok(()) => {
+                self.game.events.record(GameEvent::PlayerJoined {
+                    name: name.clone(),
+                    stream_id: client_id.clone(),
+                });
+                SubscribeResult::Ok
+            }
+            Err(err) => {
+                info!(
+                    "Subscription of {:?} as {:?} rejected: {:?}",
+                    client_id, name, err
+                );
+                SubscribeResult::Err(err)
+            }
         };
-        let answer = MessageType::unicast(answer, client_id.clone());
-        self.game
-            .players
-            .activate_player(client_id.as_str(), name.as_str());
+        let answer = MessageType::unicast(Message::SubscribeResult(result), client_id);
         trace!("Answer: {:?}", answer);
         trace!("game: {:?}", self.game);
         answer
@@ -58,14 +67,6 @@ impl MessageHandler {
         answer
     }
 
-    fn handle_start_game(&self) -> MessageType {
-        let start_game_message = Message::PublicLeaderBoard(self.game.get_players());
-        debug!("Start Game Message: {:?}", start_game_message);
-        let answer = MessageType::boardcast(start_game_message);
-        trace!("Answer: {:?}", answer);
-        answer
-    }
-
     fn handle_end_of_communication(&self, client_id: String) -> MessageType {
         info!("end of communication with client id: {:?}", client_id);
         let answer = MessageType::unicast(Message::EndOfCommunication, client_id);
@@ -82,20 +83,46 @@ impl MessageHandler {
     ) -> MessageType {
         match challenge {
             Some(challenge) => {
-                if self.has_pass_challenge(answer, &challenge) {
-                    self.game.update_winner(client_id.as_str());
-                }
-                let challenge_result = ReportedChallengeResult {
-                    name: get_name_of_challenge_type(&self.game.game_type),
-                    value: ChallengeValue::Ok {
-                        used_time: 0.0,
+                let used_time = self.game.get_challenge_elapsed().as_secs_f64();
+                let name = match self.game.players.get_player_by_stream_id(&client_id) {
+                    Some(player) => player.name,
+                    None => client_id.clone(),
+                };
+                self.game.events.record(GameEvent::AnswerReceived {
+                    player: name.clone(),
+                    answer: answer.clone(),
+                    used_time,
+                    next_target: next_target.clone(),
+                });
+                let passed = self.has_pass_challenge(answer, &challenge);
+                self.game.events.record(GameEvent::AnswerVerified {
+                    player: name.clone(),
+                    passed,
+                });
+                let value = if passed {
+                    self.game.update_winner(client_id.as_str(), used_time);
+                    self.game.record_used_time(&name, used_time);
+                    ChallengeValue::Ok {
+                        used_time,
+                        next_target,
+                    }
+                } else {
+                    info!("Player {:?} sent a bad result", name);
+                    self.game
+                        .update_score(name.as_str(), ScoreReason::BadResult);
+                    // A wrong answer counts as a challenge too hard to solve in time
+                    self.game
+                        .record_used_time(&name, self.game.challenge_timeout.as_secs_f64());
+                    ChallengeValue::BadResult {
+                        used_time,
                         next_target,
-                    },
+                    }
                 };
-                self.game.push_reported_challenge_result(challenge_result);
+                self.game
+                    .push_reported_challenge_result(ReportedChallengeResult { name, value });
                 trace!("get chain: {:?}", self.game.get_chain());
                 MessageType::boardcast(Message::RoundSummary {
-                    challenge: get_name_of_challenge_type(&self.game.game_type),
+                    challenge: challenge.name.clone(),
                     chain: self.game.get_chain(),
                 })
             }
@@ -106,77 +133,239 @@ impl MessageHandler {
         }
     }
     fn has_pass_challenge(&self, answer: ChallengeAnswer, challenge: &ChallengeType) -> bool {
-        match answer {
-            ChallengeAnswer::MD5HashCash(output) => match challenge {
-                ChallengeType::MD5HashCash(challenge) => challenge.verify(output),
-                _ => panic!("Challenge is not MD5HashCash"),
-            },
-            ChallengeAnswer::RecoverSecret(output) => match challenge {
-                ChallengeType::RecoverSecret(challenge) => challenge.verify(output),
-                _ => panic!("Challenge is not RecoverSecret"),
-            },
-            ChallengeAnswer::MonstrousMaze(output) => match challenge {
-                ChallengeType::MonstrousMaze(challenge) => challenge.verify(output),
-                _ => panic!("Challenge is not MonstrousMaze"),
-            },
+        if answer.name != challenge.name {
+            warn!("Answer {:?} does not match the pending challenge", answer);
+            return false;
         }
+        registry().verify(challenge, &answer)
     }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use std::sync::Once;
-
-//     static INIT: Once = Once::new();
-
-//     pub fn setup() -> () {
-//       INIT.call_once(|| {
-//         std::env::set_var("RUST_LOG", "trace");
-//       });
-//   }
-
-//     #[test]
-//   fn test_handle_hello() {
-//     setup();
-//     let mut handler = MessageHandler::new(PlayerList::new());
-//     println!("{:?}", handler);
-//     let message = Message::Hello;
-//     let answer = handler.handle_message(message);
-//     debug!("Answer: {:?}", answer);
-//     let _expected = MessageType::unicast(Message::Welcome { version: 1 });
-//     assert!(matches!(answer, _expected));
-//   }
-
-//   #[test]
-//   fn test_handle_subscribe() {
-//     setup();
-//     let mut handler = MessageHandler::new(PlayerList::new());
-//     let message = Message::Subscribe { name: "John".to_owned() };
-//     let answer = handler.handle_message(message);
-//     debug!("Answer: {:?}", answer);
-//     let _expected = MessageType::unicast(Message::SubscribeResult(SubscribeResult::Ok));
-//     assert!(matches!(answer, _expected));
-//   }
-
-//   #[test]
-//   fn test_handle_subscribe_already_registered() {
-//     setup();
-//     let mut handler = MessageHandler::new(PlayerList::new());
-//     handler.players.add_player(Player::new(PublicPlayer::new("John".to_owned()), TcpStream::new()));
-//     let answer = handler.handle_subscribtion("John".to_owned());
-//     let _expected = MessageType::unicast(Message::SubscribeResult(SubscribeResult::Err(SubscribeError::AlreadyRegistered)));
-//     assert!(matches!(answer, _expected));
-//   }
-
-//   #[test]
-//   fn test_handle_start_game() {
-//     setup();
-//     let handler = MessageHandler::new(PlayerList::new());
-//     handler.players.add_player(Player::new(PublicPlayer::new("John".to_owned()), TcpStream::new()));
-//     let answer = handler.handle_start_game();
-//     debug!("Answer: {:?}", answer);
-//     let _expected = MessageType::boardcast(Message::PublicLeaderBoard(vec![PublicPlayer::new("John".to_owned())]));
-//     assert!(matches!(answer, _expected));
-//   }
-// }
+#[cfg(test)]
+mod tests {
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+
+    use recover_secret::challenge_generator::DEFAULT_COMPLEXITY;
+    use serde_json::json;
+    use shared::public_player::PublicPlayer;
+    use shared::subscribe::SubscribeError;
+
+    use crate::event_log::EventLog;
+    use crate::player::{Player, MAX_NAME_LENGTH};
+    use crate::rotation::{ChallengeRotation, GameMix, RotationPolicy};
+
+    use super::*;
+
+    fn new_handler() -> MessageHandler {
+        let game = Game::new(
+            ChallengeRotation::new(GameMix::default(), RotationPolicy::RoundRobin),
+            Duration::from_secs(3),
+            Duration::from_secs(2),
+            10,
+            DEFAULT_COMPLEXITY,
+            None,
+            EventLog::disabled(),
+        );
+        MessageHandler::new(game)
+    }
+
+    /// Registers a connected but not yet subscribed player, like `Server::listen` does
+    fn connect(handler: &mut MessageHandler) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, address) = listener.accept().unwrap();
+        let stream_id = address.to_string();
+        handler.game.add_player(Player::new(
+            PublicPlayer::new(stream_id.clone(), stream_id.clone()),
+            stream,
+        ));
+        stream_id
+    }
+
+    fn subscribe(handler: &mut MessageHandler, client_id: &str, name: &str) -> SubscribeResult {
+        let message = Message::Subscribe {
+            name: name.to_string(),
+        };
+        match handler
+            .handle_message(message, client_id.to_string(), None)
+            .message
+        {
+            Message::SubscribeResult(result) => result,
+            other => panic!("Unexpected answer {:?}", other),
+        }
+    }
+
+    fn is_active(handler: &MessageHandler, client_id: &str) -> bool {
+        handler
+            .game
+            .players
+            .get_player_by_stream_id(client_id)
+            .unwrap()
+            .is_active
+    }
+
+    #[test]
+    fn test_handle_subscribe() {
+        let mut handler = new_handler();
+        let client_id = connect(&mut handler);
+        assert!(matches!(
+            subscribe(&mut handler, &client_id, "free_patato-42"),
+            SubscribeResult::Ok
+        ));
+        let player = handler.game.get_player_by_name("free_patato-42").unwrap();
+        assert!(player.is_active);
+        assert_eq!(player.stream_id, client_id);
+    }
+
+    #[test]
+    fn test_handle_subscribe_invalid_length() {
+        let mut handler = new_handler();
+        let client_id = connect(&mut handler);
+        let too_long = "a".repeat(MAX_NAME_LENGTH + 1);
+        for name in ["", too_long.as_str()] {
+            assert!(matches!(
+                subscribe(&mut handler, &client_id, name),
+                SubscribeResult::Err(SubscribeError::InvalidName)
+            ));
+        }
+        assert!(!is_active(&handler, &client_id));
+        let longest = "a".repeat(MAX_NAME_LENGTH);
+        assert!(matches!(
+            subscribe(&mut handler, &client_id, &longest),
+            SubscribeResult::Ok
+        ));
+    }
+
+    #[test]
+    fn test_handle_subscribe_invalid_characters() {
+        let mut handler = new_handler();
+        let client_id = connect(&mut handler);
+        for name in ["john doe", "john\n", "jöhn", "127.0.0.1:1234", "<script>"] {
+            assert!(matches!(
+                subscribe(&mut handler, &client_id, name),
+                SubscribeResult::Err(SubscribeError::InvalidName)
+            ));
+        }
+        assert!(!is_active(&handler, &client_id));
+    }
+
+    #[test]
+    fn test_handle_subscribe_reserved_name() {
+        let mut handler = new_handler();
+        let client_id = connect(&mut handler);
+        for name in ["server", "Admin", "OPERATOR"] {
+            assert!(matches!(
+                subscribe(&mut handler, &client_id, name),
+                SubscribeResult::Err(SubscribeError::InvalidName)
+            ));
+        }
+        assert!(!is_active(&handler, &client_id));
+    }
+
+    #[test]
+    fn test_handle_subscribe_already_registered() {
+        let mut handler = new_handler();
+        let john = connect(&mut handler);
+        let impostor = connect(&mut handler);
+        assert!(matches!(
+            subscribe(&mut handler, &john, "John"),
+            SubscribeResult::Ok
+        ));
+        for name in ["John", "john"] {
+            assert!(matches!(
+                subscribe(&mut handler, &impostor, name),
+                SubscribeResult::Err(SubscribeError::AlreadyRegistered)
+            ));
+        }
+        assert!(!is_active(&handler, &impostor));
+        let player = handler.game.get_player_by_name("John").unwrap();
+        assert_eq!(player.stream_id, john);
+    }
+
+    #[test]
+    fn test_handle_subscribe_retry_after_rejection() {
+        let mut handler = new_handler();
+        let john = connect(&mut handler);
+        let other = connect(&mut handler);
+        subscribe(&mut handler, &john, "John");
+        assert!(matches!(
+            subscribe(&mut handler, &other, "John"),
+            SubscribeResult::Err(SubscribeError::AlreadyRegistered)
+        ));
+        assert!(matches!(
+            subscribe(&mut handler, &other, "Jane"),
+            SubscribeResult::Ok
+        ));
+        assert!(is_active(&handler, &other));
+    }
+
+    #[test]
+    fn test_handle_subscribe_twice() {
+        let mut handler = new_handler();
+        let client_id = connect(&mut handler);
+        subscribe(&mut handler, &client_id, "John");
+        assert!(matches!(
+            subscribe(&mut handler, &client_id, "Jane"),
+            SubscribeResult::Err(SubscribeError::AlreadyRegistered)
+        ));
+        assert!(handler.game.get_player_by_name("Jane").is_none());
+        assert!(handler.game.get_player_by_name("John").unwrap().is_active);
+    }
+
+    #[test]
+    fn test_handle_subscribe_after_disable() {
+        let mut handler = new_handler();
+        let client_id = connect(&mut handler);
+        subscribe(&mut handler, &client_id, "John");
+        handler.game.players.disable_player(client_id.clone());
+        for name in ["John", "Jane"] {
+            assert!(matches!(
+                subscribe(&mut handler, &client_id, name),
+                SubscribeResult::Err(SubscribeError::AlreadyRegistered)
+            ));
+        }
+        assert!(!is_active(&handler, &client_id));
+        assert!(handler.game.get_player_by_name("Jane").is_none());
+    }
+
+    fn answer_maze(handler: &mut MessageHandler, client_id: &str, path: &str) -> Message {
+        let challenge = ChallengeType {
+            name: "MonstrousMaze".to_string(),
+            input: json!({"grid": "Y X", "endurance": 2}),
+        };
+        let answer = ChallengeAnswer {
+            name: "MonstrousMaze".to_string(),
+            output: json!({ "path": path }),
+        };
+        let message = Message::ChallengeResult {
+            answer,
+            next_target: "John".to_string(),
+        };
+        handler
+            .handle_message(message, client_id.to_string(), Some(challenge))
+            .message
+    }
+
+    #[test]
+    fn test_handle_challenge_result_of_the_issued_challenge() {
+        // The mix only plays hash cash, the issued maze is the one answered
+        let mut handler = new_handler();
+        let client_id = connect(&mut handler);
+        subscribe(&mut handler, &client_id, "John");
+        handler.game.start_round("monstrous-maze".to_string());
+        match answer_maze(&mut handler, &client_id, ">>") {
+            Message::RoundSummary { challenge, chain } => {
+                assert_eq!(challenge, "MonstrousMaze");
+                assert!(matches!(chain[0].value, ChallengeValue::Ok { .. }));
+            }
+            other => panic!("Unexpected answer {:?}", other),
+        }
+        match answer_maze(&mut handler, &client_id, ">") {
+            Message::RoundSummary { chain, .. } => {
+                assert!(matches!(chain[1].value, ChallengeValue::BadResult { .. }));
+            }
+            other => panic!("Unexpected answer {:?}", other),
+        }
+    }
+}
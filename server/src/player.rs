@@ -1,14 +1,38 @@
 extern crate rand;
 use rand::prelude::IteratorRandom;
-use shared::public_player::PublicPlayer;
+use rand::Rng;
+use shared::{public_player::PublicPlayer, subscribe::SubscribeError};
 use std::{
-    net::TcpStream,
+    net::{Shutdown, TcpStream},
     sync::{Arc, Mutex},
 };
+pub const MAX_NAME_LENGTH: usize = 20;
+
+/// Names kept for the server side of the game, compared case-insensitively
+pub const RESERVED_NAMES: [&str; 4] = ["server", "admin", "operator", "all"];
+
+/// A name has 1 to `MAX_NAME_LENGTH` ASCII letters, digits, `_` or `-` and is not reserved
+pub fn check_player_name(name: &str) -> Result<(), SubscribeError> {
+    let has_valid_length = !name.is_empty() && name.len() <= MAX_NAME_LENGTH;
+    let has_valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    let is_reserved = RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name));
+    if has_valid_length && has_valid_chars && !is_reserved {
+        Ok(())
+    } else {
+        Err(SubscribeError::InvalidName)
+    }
+}
+
 #[derive(Debug)]
 pub struct Player {
     pub info_public: PublicPlayer,
     pub tcp_stream: TcpStream,
+    /// The stream subscribed once, it stays set when the player is disabled or kicked
+    pub is_subscribed: bool,
 }
 
 impl Player {
@@ -16,6 +40,7 @@ impl Player {
         Player {
             info_public,
             tcp_stream,
+            is_subscribed: false,
         }
     }
 }
@@ -45,20 +70,12 @@ impl PlayerList {
             .collect()
     }
 
-    pub fn has_player_with_name(&self, name: &str) -> bool {
-        self.players
-            .lock()
-            .unwrap()
-            .iter()
-            .any(|p| p.info_public.name == name)
-    }
-
-    pub fn pick_random_active_player(&self) -> Option<PublicPlayer> {
+    pub fn pick_random_active_player<R: Rng>(&self, rng: &mut R) -> Option<PublicPlayer> {
         let players = self.players.lock().unwrap();
         players
             .iter()
             .filter(|p| p.info_public.is_active)
-            .choose(&mut rand::thread_rng())
+            .choose(rng)
             .map(|p| p.info_public.clone())
     }
 
@@ -71,17 +88,33 @@ impl PlayerList {
             .map(|p| p.info_public.clone())
     }
 
-    pub fn get_and_remove_player_by_stream_id(&self, stream_id: String) -> Option<Player> {
-        let index = self
-            .players
+    pub fn get_player_by_stream_id(&self, stream_id: &str) -> Option<PublicPlayer> {
+        self.players
             .lock()
             .unwrap()
             .iter()
-            .position(|p| p.info_public.stream_id == stream_id);
-        if let Some(index) = index {
-            Some(self.players.lock().unwrap().remove(index))
-        } else {
-            None
+            .find(|p| p.info_public.stream_id == stream_id)
+            .map(|p| p.info_public.clone())
+    }
+
+    /// Activates the player of the stream under `name`, a rejected player stays inactive and a
+    /// stream subscribes only once: a disabled player cannot come back
+    pub fn subscribe_player(&self, client_id: &str, name: &str) -> Result<(), SubscribeError> {
+        check_player_name(name)?;
+        let mut players = self.players.lock().unwrap();
+        let is_taken = players.iter().any(|p| {
+            p.info_public.stream_id != client_id && p.info_public.name.eq_ignore_ascii_case(name)
+        });
+        let player = players
+            .iter_mut()
+            .find(|p| p.info_public.stream_id == client_id);
+        match player {
+            Some(player) if !is_taken && !player.is_subscribed => {
+                player.is_subscribed = true;
+                player.info_public.make_active(name);
+                Ok(())
+            }
+            _ => Err(SubscribeError::AlreadyRegistered),
         }
     }
 
@@ -96,20 +129,6 @@ impl PlayerList {
             self.players.lock().unwrap()[index].info_public.is_active = false;
         }
     }
-    pub fn activate_player(&mut self, client_id: &str, name: &str) {
-        let index = self
-            .players
-            .lock()
-            .unwrap()
-            .iter()
-            .position(|p| p.info_public.stream_id == client_id);
-        if let Some(index) = index {
-            self.players.lock().unwrap()[index]
-                .info_public
-                .make_active(name);
-        }
-    }
-
     pub fn decrease_score(&self, name: &str) {
         let index = self
             .players
@@ -121,4 +140,17 @@ impl PlayerList {
             self.players.lock().unwrap()[index].info_public.score -= 1;
         }
     }
+
+    /// Disables the player and closes its stream, returns `false` if no player has this name
+    pub fn kick_player(&self, name: &str) -> bool {
+        let mut players = self.players.lock().unwrap();
+        match players.iter_mut().find(|p| p.info_public.name == name) {
+            Some(player) => {
+                player.info_public.is_active = false;
+                let _ = player.tcp_stream.shutdown(Shutdown::Both);
+                true
+            }
+            None => false,
+        }
+    }
 }
@@ -0,0 +1,199 @@
+use std::str::FromStr;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+use shared::registry::registry;
+
+/// Game types played in a game with their weights, parsed from `hash-cash:2,monstrous-maze:1`.
+/// A game type without weight weighs 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameMix {
+    pub game_types: Vec<(String, u32)>,
+}
+
+impl Default for GameMix {
+    fn default() -> Self {
+        GameMix {
+            game_types: vec![("hash-cash".to_string(), 1)],
+        }
+    }
+}
+
+impl FromStr for GameMix {
+    type Err = String;
+
+    fn from_str(mix: &str) -> Result<Self, Self::Err> {
+        let mut game_types: Vec<(String, u32)> = Vec::new();
+        for entry in mix.split(',') {
+            let (game_type, weight) = match entry.split_once(':') {
+                Some((game_type, weight)) => {
+                    let weight = weight
+                        .parse()
+                        .map_err(|_| format!("invalid weight {:?} of {}", weight, game_type))?;
+                    (game_type, weight)
+                }
+                None => (entry, 1),
+            };
+            if registry().get_by_game_type(game_type).is_none() {
+                return Err(format!(
+                    "unknown game type {:?}, expected one of {}",
+                    game_type,
+                    registry().game_types().join(", ")
+                ));
+            }
+            if weight == 0 {
+                return Err(format!("the weight of {} must be positive", game_type));
+            }
+            if game_types.iter().any(|(known, _)| known == game_type) {
+                return Err(format!("{} is listed twice", game_type));
+            }
+            game_types.push((game_type.to_string(), weight));
+        }
+        Ok(GameMix { game_types })
+    }
+}
+
+impl GameMix {
+    /// Game types repeated as many times as their weight, in the order of the mix
+    fn sequence(&self) -> Vec<&str> {
+        self.game_types
+            .iter()
+            .flat_map(|(game_type, weight)| (0..*weight).map(move |_| game_type.as_str()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationPolicy {
+    /// Cycles through the mix, each game type played `weight` rounds in a row
+    RoundRobin,
+    /// Draws the game type of each round with a probability proportional to its weight
+    Random,
+    /// Plays `weight` rounds of each game type in the order of the mix, then stays on the last one
+    Escalating,
+}
+
+impl From<&str> for RotationPolicy {
+    fn from(s: &str) -> Self {
+        match s {
+            "round-robin" => RotationPolicy::RoundRobin,
+            "random" => RotationPolicy::Random,
+            "escalating" => RotationPolicy::Escalating,
+            _ => panic!("Unknown rotation policy"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChallengeRotation {
+    pub mix: GameMix,
+    pub policy: RotationPolicy,
+}
+
+impl ChallengeRotation {
+    pub fn new(mix: GameMix, policy: RotationPolicy) -> ChallengeRotation {
+        ChallengeRotation { mix, policy }
+    }
+
+    /// Game type of the round, `round` counts the rounds already played
+    pub fn game_type_of_round<R: Rng>(&self, round: usize, rng: &mut R) -> String {
+        let sequence = self.mix.sequence();
+        let game_type = match self.policy {
+            RotationPolicy::RoundRobin => sequence[round % sequence.len()],
+            RotationPolicy::Escalating => sequence[round.min(sequence.len() - 1)],
+            RotationPolicy::Random => {
+                let weights = self.mix.game_types.iter().map(|(_, weight)| *weight);
+                let index = WeightedIndex::new(weights)
+                    .expect("The weights of a mix are positive")
+                    .sample(rng);
+                &self.mix.game_types[index].0
+            }
+        };
+        game_type.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn rotation(mix: &str, policy: RotationPolicy) -> ChallengeRotation {
+        ChallengeRotation::new(mix.parse().unwrap(), policy)
+    }
+
+    fn rounds(rotation: &ChallengeRotation, count: usize) -> Vec<String> {
+        let mut rng = StdRng::seed_from_u64(22);
+        (0..count)
+            .map(|round| rotation.game_type_of_round(round, &mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_game_mix() {
+        assert_eq!("hash-cash".parse(), Ok(GameMix::default()));
+        assert_eq!(
+            "hash-cash:2,monstrous-maze".parse::<GameMix>().unwrap(),
+            GameMix {
+                game_types: vec![
+                    ("hash-cash".to_string(), 2),
+                    ("monstrous-maze".to_string(), 1)
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_game_mix() {
+        for mix in [
+            "",
+            "chess",
+            "hash-cash:0",
+            "hash-cash:-1",
+            "hash-cash:two",
+            "hash-cash,nonogram,hash-cash:2",
+        ] {
+            assert!(mix.parse::<GameMix>().is_err(), "{:?}", mix);
+        }
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let rotation = rotation("hash-cash:2,bloxorz", RotationPolicy::RoundRobin);
+        assert_eq!(
+            rounds(&rotation, 6),
+            [
+                "hash-cash",
+                "hash-cash",
+                "bloxorz",
+                "hash-cash",
+                "hash-cash",
+                "bloxorz"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escalating() {
+        let rotation = rotation("hash-cash:2,bloxorz", RotationPolicy::Escalating);
+        assert_eq!(
+            rounds(&rotation, 5),
+            ["hash-cash", "hash-cash", "bloxorz", "bloxorz", "bloxorz"]
+        );
+    }
+
+    #[test]
+    fn test_random() {
+        let rotation = rotation("hash-cash:3,bloxorz", RotationPolicy::Random);
+        let rounds = rounds(&rotation, 400);
+        let hash_cash = rounds.iter().filter(|name| *name == "hash-cash").count();
+        assert_eq!(
+            rounds.iter().filter(|name| *name == "bloxorz").count(),
+            400 - hash_cash
+        );
+        assert!((250..350).contains(&hash_cash), "{}", hash_cash);
+    }
+}
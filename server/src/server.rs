@@ -1,14 +1,20 @@
+use crate::console::Console;
 use crate::exchanger::Exchanger;
 use crate::game::Game;
 use crate::message_handler::MessageHandler;
-use crate::player::Player;
+use crate::player::{Player, PlayerList};
 use crate::utils::send_response;
 use log::{debug, error, info, trace, warn};
-use shared::message::{MessageType, ResponseType};
+use shared::message::{Message, MessageType, ResponseType};
 use shared::public_player::PublicPlayer;
-use std::net::TcpListener;
+use std::io::{self, ErrorKind};
+use std::net::{Shutdown, TcpListener};
 use std::sync::mpsc;
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Pause between two polls of the listener for incoming players
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 pub struct Server {
     listener: TcpListener,
@@ -24,10 +30,35 @@ impl Server {
         let mut handles: Vec<JoinHandle<()>> = Vec::new();
         let (tx, rx) = mpsc::channel::<MessageType>();
 
-        handles.push(self.listen_broadcast(rx));
+        // Neither joined: the console blocks on stdin and keeps the broadcast channel open,
+        // player threads only stop once the broadcast of `EndOfGame` closed their streams
+        self.listen_broadcast(rx);
+        self.listen_console(tx.clone());
+        handles.push(self.listen_challenge_timeout(tx.clone()));
 
-        for stream in self.listener.incoming() {
-            let stream = stream.expect("Failed to accept stream");
+        self.listener
+            .set_nonblocking(true)
+            .expect("Cannot set listener in non-blocking mode");
+        while !self.game.is_over() {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                    continue;
+                }
+                Err(err) => {
+                    error!("Failed to accept stream : {:?}", err);
+                    continue;
+                }
+            };
+            if self.game.is_lobby_sealed() {
+                info!("Lobby is sealed, rejecting {:?}", stream.peer_addr());
+                let _ = stream.shutdown(Shutdown::Both);
+                continue;
+            }
+            stream
+                .set_nonblocking(false)
+                .expect("Cannot set stream in blocking mode");
             let stream_id = stream
                 .peer_addr()
                 .expect("Cannot retrieve client address")
@@ -53,39 +84,70 @@ impl Server {
         }
     }
 
+    fn listen_challenge_timeout(&self, tx: mpsc::Sender<MessageType>) -> JoinHandle<()> {
+        let game = self.game.clone();
+        thread::spawn(move || {
+            let message_handler = MessageHandler::new(game.clone());
+            let mut exchanger = Exchanger::new(message_handler, game, tx);
+            exchanger.watch_challenge_timeout();
+        })
+    }
+
+    fn listen_console(&self, tx: mpsc::Sender<MessageType>) -> JoinHandle<()> {
+        let game = self.game.clone();
+        thread::spawn(move || {
+            let message_handler = MessageHandler::new(game.clone());
+            let exchanger = Exchanger::new(message_handler, game.clone(), tx);
+            Console::new(exchanger, game).run(io::stdin().lock());
+        })
+    }
+
     fn listen_broadcast(&self, rx: mpsc::Receiver<MessageType>) -> JoinHandle<()> {
-        let mut players = self.game.players.clone();
+        let players = self.game.players.clone();
         info!("players {:?}", self.game.get_players());
         thread::spawn(move || loop {
             match rx.recv() {
                 Ok(msg) => {
                     debug!("Sending : {:?}", &msg);
+                    let is_end_of_game = matches!(msg.message, Message::EndOfGame { .. });
                     match msg.message_type {
                         ResponseType::Broadcast => {
                             let mut players = players.players.lock().unwrap();
                             for player in players.iter_mut().filter(|p| p.info_public.is_active) {
                                 debug!("broadcast to {:?}", &player.info_public.name);
-                                send_response(msg.message.clone(), &player.tcp_stream);
+                                if let Err(err) =
+                                    send_response(msg.message.clone(), &player.tcp_stream)
+                                {
+                                    warn!(
+                                        "Cannot send message to {:?}: {}",
+                                        &player.info_public.name, err
+                                    );
+                                }
                             }
                         }
                         ResponseType::Unicast { client_id } => {
                             trace!("unicast to {:?}", &client_id);
-                            let player =
-                                players.get_and_remove_player_by_stream_id(client_id.clone());
-                            debug!("players {:?}", players);
+                            let players = players.players.lock().unwrap();
+                            let player = players
+                                .iter()
+                                .find(|p| p.info_public.stream_id == client_id);
                             match player {
                                 Some(player) => {
-                                    send_response(msg.message, &player.tcp_stream);
-                                    players.add_player(player);
-                                    debug!("players {:?}", players);
+                                    if let Err(err) = send_response(msg.message, &player.tcp_stream)
+                                    {
+                                        warn!("Cannot send message to {:?}: {}", client_id, err);
+                                    }
                                 }
                                 None => warn!("player {} not found", client_id),
                             }
                         }
                     };
+                    if is_end_of_game {
+                        close_player_streams(&players);
+                    }
                 }
-                Err(err) => {
-                    error!("rx receive error : {:?}", err);
+                Err(_) => {
+                    debug!("All senders are closed, stop broadcasting");
                     break;
                 }
             }
@@ -93,6 +155,18 @@ impl Server {
     }
 }
 
+fn close_player_streams(players: &PlayerList) {
+    for player in players.players.lock().unwrap().iter() {
+        if let Err(err) = player.tcp_stream.shutdown(Shutdown::Both) {
+            trace!(
+                "Shutdown failed for {:?}: {:?}",
+                player.info_public.name,
+                err
+            );
+        }
+    }
+}
+
 pub fn create_listener(address: String) -> TcpListener {
     info!("Start Listening on : {}", &address);
     let listener = TcpListener::bind(address);
@@ -1,21 +1,32 @@
-use std::collections::HashSet;
-
-use serde::{Deserialize, Serialize};
+use serde::de::Error;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
 
+use bloxorz::challenge_generator::validate_bloxorz_challenge;
+use bloxorz::challenge_resolve::solve_bloxorz;
+use bloxorz::models::{Bloxorz, BloxorzInput, BloxorzOutput};
 use hashcash::{
-    dto::{MD5HashCash, MD5HashCashInput, MD5HashCashOutput},
+    algorithm::HashAlgorithm,
+    dto::{HashCashInput, HashCashOutput, MD5HashCash, SHA1HashCash, SHA256HashCash},
     hashcash::Hashcash,
+    solver::SolverConfig,
 };
 use monstrous_maze::challenge_generator::validate_maze_challenge;
 use monstrous_maze::challenge_resolve::MonstrousMazeResolver;
 use monstrous_maze::models::{MonstrousMaze, MonstrousMazeInput, MonstrousMazeOutput};
+use nonogram::challenge_generator::validate_nonogram_challenge;
+use nonogram::challenge_resolve::solve_nonogram;
+use nonogram::models::{Nonogram, NonogramInput, NonogramOutput};
 use recover_secret::challenge_generator::validate_challenge;
 use recover_secret::challenge_resolve::{
-    solve_secret_sentence_challenge, solve_secret_sentence_challenge_cheat,
-    solve_secret_string_challenge, solve_secret_string_challenge_cheat,
+    solve_secret_sentence_challenge, solve_secret_string_challenge,
 };
+use recover_secret::dictionary::Trie;
 use recover_secret::models::{RecoverSecret, RecoverSecretInput, RecoverSecretOutput};
 
+use crate::registry::{registry, SolverContext};
+
 pub trait Challenge {
     /// Données en entrée du challenge
     type Input;
@@ -29,17 +40,19 @@ pub trait Challenge {
     fn solve(&self) -> Self::Output;
     /// Vérifie qu'une sortie est valide pour le challenge
     fn verify(&self, answer: Self::Output) -> bool;
+    /// Résout le challenge avec les ressources du client, par défaut comme `solve`
+    fn solve_with(&self, _context: &SolverContext) -> Self::Output {
+        self.solve()
+    }
 }
 
 pub trait DictionaryChallenge: Challenge {
-    fn solve_secret(&self, dictionary_hashmap: &HashSet<String>) -> Self::Output;
-    fn solve_cheat(&self) -> Self::Output;
-    fn solve_secret_cheat(&self) -> Self::Output;
+    fn solve_secret(&self, dictionary: &Trie) -> Self::Output;
 }
 
 impl Challenge for MD5HashCash {
-    type Input = MD5HashCashInput;
-    type Output = MD5HashCashOutput;
+    type Input = HashCashInput;
+    type Output = HashCashOutput;
 
     fn name() -> String {
         "MD5HashCash".to_string()
@@ -54,21 +67,101 @@ impl Challenge for MD5HashCash {
     }
 
     fn verify(&self, result: Self::Output) -> bool {
-        Hashcash::verify(result.hashcode.clone(), self.0.complexity)
+        Hashcash::verify(&self.0.message, self.0.complexity, &result)
+    }
+
+    fn solve_with(&self, context: &SolverContext) -> Self::Output {
+        match context.hashcash_solver {
+            Some(solver) => solver.solve(&self.0.message, self.0.complexity),
+            None => self.solve(),
+        }
     }
 }
 
-impl DictionaryChallenge for RecoverSecret {
-    fn solve_secret(&self, dictionary_hashmap: &HashSet<String>) -> Self::Output {
-        solve_secret_sentence_challenge(&self.0, dictionary_hashmap)
+impl Challenge for SHA1HashCash {
+    type Input = HashCashInput;
+    type Output = HashCashOutput;
+
+    fn name() -> String {
+        "SHA1HashCash".to_string()
     }
 
-    fn solve_cheat(&self) -> Self::Output {
-        solve_secret_string_challenge_cheat(&self.0)
+    fn new(input: Self::Input) -> Self {
+        SHA1HashCash(input)
     }
 
-    fn solve_secret_cheat(&self) -> Self::Output {
-        solve_secret_sentence_challenge_cheat()
+    fn solve(&self) -> Self::Output {
+        Hashcash::solve_hash(
+            HashAlgorithm::SHA1,
+            self.0.message.clone(),
+            self.0.complexity,
+            &SolverConfig::default(),
+        )
+    }
+
+    fn verify(&self, result: Self::Output) -> bool {
+        Hashcash::verify_hash(
+            HashAlgorithm::SHA1,
+            &self.0.message,
+            self.0.complexity,
+            &result,
+        )
+    }
+
+    fn solve_with(&self, context: &SolverContext) -> Self::Output {
+        match context.hashcash_solver {
+            Some(solver) => {
+                solver.solve_hash(HashAlgorithm::SHA1, &self.0.message, self.0.complexity)
+            }
+            None => self.solve(),
+        }
+    }
+}
+
+impl Challenge for SHA256HashCash {
+    type Input = HashCashInput;
+    type Output = HashCashOutput;
+
+    fn name() -> String {
+        "SHA256HashCash".to_string()
+    }
+
+    fn new(input: Self::Input) -> Self {
+        SHA256HashCash(input)
+    }
+
+    fn solve(&self) -> Self::Output {
+        Hashcash::solve_hash(
+            HashAlgorithm::SHA256,
+            self.0.message.clone(),
+            self.0.complexity,
+            &SolverConfig::default(),
+        )
+    }
+
+    fn verify(&self, result: Self::Output) -> bool {
+        Hashcash::verify_hash(
+            HashAlgorithm::SHA256,
+            &self.0.message,
+            self.0.complexity,
+            &result,
+        )
+    }
+
+    fn solve_with(&self, context: &SolverContext) -> Self::Output {
+        match context.hashcash_solver {
+            Some(solver) => {
+                solver.solve_hash(HashAlgorithm::SHA256, &self.0.message, self.0.complexity)
+            }
+            None => self.solve(),
+        }
+    }
+}
+
+impl DictionaryChallenge for RecoverSecret {
+    fn solve_secret(&self, dictionary: &Trie) -> Self::Output {
+        // Falls back on the letters only answer when no sentence of dictionary words is found
+        solve_secret_sentence_challenge(&self.0, dictionary).unwrap_or_else(|_| self.solve())
     }
 }
 
@@ -85,12 +178,22 @@ impl Challenge for RecoverSecret {
     }
 
     fn solve(&self) -> Self::Output {
-        solve_secret_string_challenge(&self.0)
+        // Contradictory tuples can only be answered with a sentence the server will reject
+        solve_secret_string_challenge(&self.0).unwrap_or(RecoverSecretOutput {
+            secret_sentence: String::new(),
+        })
     }
 
     fn verify(&self, result: Self::Output) -> bool {
         validate_challenge(&self.0, &result)
     }
+
+    fn solve_with(&self, context: &SolverContext) -> Self::Output {
+        match context.dictionary {
+            Some(dictionary) => self.solve_secret(dictionary),
+            None => self.solve(),
+        }
+    }
 }
 
 impl Challenge for MonstrousMaze {
@@ -106,7 +209,12 @@ impl Challenge for MonstrousMaze {
     }
 
     fn solve(&self) -> Self::Output {
-        MonstrousMazeResolver::resolve_monstrous_maze_challenge(&self.0)
+        // An unsolvable maze can only be answered with a path the server will reject
+        MonstrousMazeResolver::resolve_monstrous_maze_challenge(&self.0).unwrap_or(
+            MonstrousMazeOutput {
+                path: String::new(),
+            },
+        )
     }
 
     fn verify(&self, answer: Self::Output) -> bool {
@@ -114,11 +222,71 @@ impl Challenge for MonstrousMaze {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum ChallengeAnswer {
-    MD5HashCash(MD5HashCashOutput),
-    RecoverSecret(RecoverSecretOutput),
-    MonstrousMaze(MonstrousMazeOutput),
+impl Challenge for Nonogram {
+    type Input = NonogramInput;
+    type Output = NonogramOutput;
+
+    fn name() -> String {
+        "Nonogram".to_string()
+    }
+
+    fn new(input: Self::Input) -> Self {
+        Nonogram(input)
+    }
+
+    fn solve(&self) -> Self::Output {
+        // Contradictory clues can only be answered with a grid the server will reject
+        solve_nonogram(&self.0).unwrap_or(NonogramOutput { grid: vec![] })
+    }
+
+    fn verify(&self, answer: Self::Output) -> bool {
+        validate_nonogram_challenge(&self.0, &answer)
+    }
+}
+
+impl Challenge for Bloxorz {
+    type Input = BloxorzInput;
+    type Output = BloxorzOutput;
+
+    fn name() -> String {
+        "Bloxorz".to_string()
+    }
+
+    fn new(input: Self::Input) -> Self {
+        Bloxorz(input)
+    }
+
+    fn solve(&self) -> Self::Output {
+        // A level without path can only be answered with rolls the server will reject
+        solve_bloxorz(&self.0).unwrap_or(BloxorzOutput {
+            path: String::new(),
+        })
+    }
+
+    fn verify(&self, answer: Self::Output) -> bool {
+        validate_bloxorz_challenge(&self.0, &answer)
+    }
+}
+
+/// Answer to a challenge: the name of the challenge and its JSON output. Serialized as
+/// `{"<name>": <output>}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChallengeAnswer {
+    pub name: String,
+    pub output: Value,
+}
+
+impl ChallengeAnswer {
+    pub fn from_output<C>(output: &C::Output) -> ChallengeAnswer
+    where
+        C: Challenge,
+        C::Output: Serialize,
+    {
+        ChallengeAnswer {
+            name: C::name(),
+            output: serde_json::to_value(output).expect("Could not serialize answer"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -135,35 +303,155 @@ pub struct ReportedChallengeResult {
     pub value: ChallengeValue,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum ChallengeType {
-    MD5HashCash(MD5HashCash),
-    RecoverSecret(RecoverSecret),
-    MonstrousMaze(MonstrousMaze),
-}
-
-#[derive(Debug, Clone)]
-pub enum GameType {
-    HashCash,
-    RecoverSecret,
-    MonstrousMaze,
-}
-
-impl From<&str> for GameType {
-    fn from(s: &str) -> Self {
-        match s {
-            "hash-cash" => GameType::HashCash,
-            "recover-secret" => GameType::RecoverSecret,
-            "monstrous-maze" => GameType::MonstrousMaze,
-            _ => panic!("Unknown game type"),
+/// Challenge sent to a player: the name of the challenge and its JSON input. Serialized as
+/// `{"<name>": <input>}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChallengeType {
+    pub name: String,
+    pub input: Value,
+}
+
+impl ChallengeType {
+    pub fn from_challenge<C: Challenge + Serialize>(challenge: &C) -> ChallengeType {
+        ChallengeType {
+            name: C::name(),
+            input: serde_json::to_value(challenge).expect("Could not serialize challenge"),
         }
     }
+
+    /// Whether the answer solves this challenge, an answer to another challenge never does
+    pub fn verify(&self, answer: ChallengeAnswer) -> bool {
+        registry().verify(self, &answer)
+    }
+
+    /// MD5 of the JSON challenge, equal challenges always share the same hash
+    pub fn canonical_hash(&self) -> String {
+        let json = serde_json::to_string(self).expect("Could not serialize challenge");
+        format!("{:x}", md5::compute(json))
+    }
+}
+
+impl Serialize for ChallengeType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_tagged(serializer, &self.name, &self.input)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChallengeType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (name, input) = deserialize_tagged(deserializer)?;
+        Ok(ChallengeType { name, input })
+    }
+}
+
+impl Serialize for ChallengeAnswer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_tagged(serializer, &self.name, &self.output)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChallengeAnswer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (name, output) = deserialize_tagged(deserializer)?;
+        Ok(ChallengeAnswer { name, output })
+    }
+}
+
+/// Same layout as an externally tagged enum variant
+fn serialize_tagged<S: Serializer>(
+    serializer: S,
+    name: &str,
+    value: &Value,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(name, value)?;
+    map.end()
+}
+
+fn deserialize_tagged<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(String, Value), D::Error> {
+    let map = Map::<String, Value>::deserialize(deserializer)?;
+    if map.len() != 1 {
+        return Err(D::Error::custom(format!(
+            "expected a single challenge name, found {} keys",
+            map.len()
+        )));
+    }
+    Ok(map.into_iter().next().expect("The map has one entry"))
 }
 
-pub fn get_name_of_challenge_type(game_type: &GameType) -> String {
-    match game_type {
-        GameType::HashCash => MD5HashCash::name(),
-        GameType::RecoverSecret => RecoverSecret::name(),
-        GameType::MonstrousMaze => MonstrousMaze::name(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maze(grid: &str) -> ChallengeType {
+        ChallengeType::from_challenge(&MonstrousMaze(MonstrousMazeInput {
+            grid: grid.to_string(),
+            endurance: 2,
+        }))
+    }
+
+    fn maze_answer(path: &str) -> ChallengeAnswer {
+        ChallengeAnswer::from_output::<MonstrousMaze>(&MonstrousMazeOutput {
+            path: path.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_verify_answer() {
+        let challenge = maze("Y X");
+        assert!(challenge.verify(maze_answer(">>")));
+        assert!(!challenge.verify(maze_answer(">")));
+        let answer = ChallengeAnswer::from_output::<RecoverSecret>(&RecoverSecretOutput {
+            secret_sentence: ">>".to_string(),
+        });
+        assert!(!challenge.verify(answer));
+    }
+
+    #[test]
+    fn test_verify_hashcash_answer_of_another_algorithm() {
+        let input = HashCashInput {
+            complexity: 4,
+            message: "hello".to_string(),
+        };
+        let challenge = ChallengeType::from_challenge(&SHA256HashCash(input.clone()));
+        let output = SHA256HashCash(input.clone()).solve();
+        assert!(challenge.verify(ChallengeAnswer::from_output::<SHA256HashCash>(&output)));
+        assert!(!challenge.verify(ChallengeAnswer::from_output::<SHA1HashCash>(&output)));
+        assert!(!SHA1HashCash(input).verify(output));
+    }
+
+    #[test]
+    fn test_canonical_hash() {
+        assert_eq!(maze("Y X").canonical_hash(), maze("Y X").canonical_hash());
+        assert_ne!(maze("Y X").canonical_hash(), maze("YX").canonical_hash());
+        assert_eq!(maze("Y X").canonical_hash().len(), 32);
+    }
+
+    #[test]
+    fn test_challenge_serialization() {
+        let challenge = ChallengeType::from_challenge(&MD5HashCash(HashCashInput {
+            complexity: 9,
+            message: "hello".to_string(),
+        }));
+        let serialized = serde_json::to_string(&challenge).unwrap();
+        assert_eq!(
+            serialized,
+            "{\"MD5HashCash\":{\"complexity\":9,\"message\":\"hello\"}}"
+        );
+        let deserialized: ChallengeType = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, challenge);
+    }
+
+    #[test]
+    fn test_answer_serialization() {
+        let answer = maze_answer(">>");
+        let serialized = serde_json::to_string(&answer).unwrap();
+        assert_eq!(serialized, "{\"MonstrousMaze\":{\"path\":\">>\"}}");
+        let deserialized: ChallengeAnswer = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, answer);
+        assert!(serde_json::from_str::<ChallengeAnswer>("{}").is_err());
+        assert!(serde_json::from_str::<ChallengeAnswer>("{\"A\":1,\"B\":2}").is_err());
     }
 }
@@ -0,0 +1,257 @@
+use std::fmt::{Display, Formatter};
+use std::io::{ErrorKind, Read, Write};
+use std::string::FromUtf8Error;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Largest frame accepted by default (16 MiB), big enough for the largest mazes
+pub const DEFAULT_MAX_FRAME_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Size in bytes of the big endian length prefix of every frame
+const FRAME_HEADER_SIZE: usize = 4;
+
+#[derive(Debug)]
+pub enum FrameError {
+    /// The peer closed the stream between two frames
+    EndOfStream,
+    /// The announced frame size is larger than the configured maximum
+    Oversized { size: u32, max_frame_size: u32 },
+    /// The frame payload is not valid UTF-8
+    InvalidUtf8(FromUtf8Error),
+    /// The frame payload is not a valid JSON message
+    InvalidJson(serde_json::Error),
+    /// Any other I/O failure, including a stream closed in the middle of a frame
+    Io(std::io::Error),
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::EndOfStream => write!(f, "end of stream"),
+            FrameError::Oversized {
+                size,
+                max_frame_size,
+            } => write!(
+                f,
+                "frame of {} bytes exceeds the maximum of {} bytes",
+                size, max_frame_size
+            ),
+            FrameError::InvalidUtf8(err) => write!(f, "invalid utf-8 frame: {}", err),
+            FrameError::InvalidJson(err) => write!(f, "invalid json frame: {}", err),
+            FrameError::Io(err) => write!(f, "i/o error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+impl From<std::io::Error> for FrameError {
+    fn from(err: std::io::Error) -> Self {
+        FrameError::Io(err)
+    }
+}
+
+/// Reads length-prefixed JSON frames: a `u32` big endian size followed by the UTF-8 payload
+pub struct FrameReader<R: Read> {
+    reader: R,
+    max_frame_size: u32,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R) -> FrameReader<R> {
+        FrameReader::with_max_frame_size(reader, DEFAULT_MAX_FRAME_SIZE)
+    }
+
+    pub fn with_max_frame_size(reader: R, max_frame_size: u32) -> FrameReader<R> {
+        FrameReader {
+            reader,
+            max_frame_size,
+        }
+    }
+
+    /// Reads the next frame and deserializes it
+    pub fn read_message<T: DeserializeOwned>(&mut self) -> Result<T, FrameError> {
+        let payload = self.read_frame()?;
+        let payload = String::from_utf8(payload).map_err(FrameError::InvalidUtf8)?;
+        serde_json::from_str(&payload).map_err(FrameError::InvalidJson)
+    }
+
+    /// Reads the raw payload of the next frame
+    pub fn read_frame(&mut self) -> Result<Vec<u8>, FrameError> {
+        let mut header = [0; FRAME_HEADER_SIZE];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                return Err(FrameError::EndOfStream)
+            }
+            Err(err) => return Err(FrameError::Io(err)),
+        }
+
+        let size = u32::from_be_bytes(header);
+        if size > self.max_frame_size {
+            return Err(FrameError::Oversized {
+                size,
+                max_frame_size: self.max_frame_size,
+            });
+        }
+
+        let mut payload = vec![0; size as usize];
+        self.reader.read_exact(&mut payload)?;
+        Ok(payload)
+    }
+}
+
+/// Writes length-prefixed JSON frames, the counterpart of [`FrameReader`]
+pub struct FrameWriter<W: Write> {
+    writer: W,
+    max_frame_size: u32,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(writer: W) -> FrameWriter<W> {
+        FrameWriter::with_max_frame_size(writer, DEFAULT_MAX_FRAME_SIZE)
+    }
+
+    pub fn with_max_frame_size(writer: W, max_frame_size: u32) -> FrameWriter<W> {
+        FrameWriter {
+            writer,
+            max_frame_size,
+        }
+    }
+
+    /// Serializes the message and writes it as a single frame
+    pub fn write_message<T: Serialize>(&mut self, message: &T) -> Result<(), FrameError> {
+        let payload = serde_json::to_string(message).map_err(FrameError::InvalidJson)?;
+        self.write_frame(payload.as_bytes())
+    }
+
+    /// Writes a raw payload as a single frame
+    pub fn write_frame(&mut self, payload: &[u8]) -> Result<(), FrameError> {
+        let size = u32::try_from(payload.len()).unwrap_or(u32::MAX);
+        if size > self.max_frame_size {
+            return Err(FrameError::Oversized {
+                size,
+                max_frame_size: self.max_frame_size,
+            });
+        }
+
+        let frame = [&size.to_be_bytes(), payload].concat();
+        self.writer.write_all(&frame)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::message::Message;
+
+    use super::*;
+
+    fn encode(payload: &[u8]) -> Vec<u8> {
+        [&(payload.len() as u32).to_be_bytes(), payload].concat()
+    }
+
+    #[test]
+    fn test_write_then_read_message() {
+        let mut buffer = Vec::new();
+        let mut writer = FrameWriter::new(&mut buffer);
+        writer.write_message(&Message::Hello).unwrap();
+        writer
+            .write_message(&Message::Subscribe {
+                name: "free_patato".to_string(),
+            })
+            .unwrap();
+
+        let mut reader = FrameReader::new(Cursor::new(buffer));
+        assert!(matches!(reader.read_message(), Ok(Message::Hello)));
+        assert!(matches!(
+            reader.read_message(),
+            Ok(Message::Subscribe { name }) if name == "free_patato"
+        ));
+        assert!(matches!(
+            reader.read_message::<Message>(),
+            Err(FrameError::EndOfStream)
+        ));
+    }
+
+    #[test]
+    fn test_frame_layout() {
+        let mut buffer = Vec::new();
+        FrameWriter::new(&mut buffer)
+            .write_message(&Message::Hello)
+            .unwrap();
+        assert_eq!(buffer, encode(b"\"Hello\""));
+    }
+
+    #[test]
+    fn test_read_large_frame() {
+        let grid = "#".repeat(200_000);
+        let payload = format!(
+            "{{\"Challenge\":{{\"MonstrousMaze\":{{\"grid\":\"{}\",\"endurance\":2}}}}}}",
+            grid
+        );
+        let mut reader = FrameReader::new(Cursor::new(encode(payload.as_bytes())));
+        match reader.read_message() {
+            Ok(Message::Challenge(_)) => {}
+            other => panic!("Unexpected frame {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_oversized_frame() {
+        let mut reader = FrameReader::with_max_frame_size(Cursor::new(encode(b"\"Hello\"")), 4);
+        assert!(matches!(
+            reader.read_message::<Message>(),
+            Err(FrameError::Oversized {
+                size: 7,
+                max_frame_size: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_write_oversized_frame() {
+        let mut buffer = Vec::new();
+        let mut writer = FrameWriter::with_max_frame_size(&mut buffer, 4);
+        assert!(matches!(
+            writer.write_message(&Message::Hello),
+            Err(FrameError::Oversized { .. })
+        ));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_read_truncated_frame() {
+        let mut frame = encode(b"\"Hello\"");
+        frame.truncate(6);
+        let mut reader = FrameReader::new(Cursor::new(frame));
+        assert!(matches!(
+            reader.read_message::<Message>(),
+            Err(FrameError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_invalid_utf8() {
+        let mut reader = FrameReader::new(Cursor::new(encode(&[0xff, 0xfe])));
+        assert!(matches!(
+            reader.read_message::<Message>(),
+            Err(FrameError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_invalid_json_keeps_stream_aligned() {
+        let buffer = [encode(b"{\"Hello"), encode(b"\"Hello\"")].concat();
+        let mut reader = FrameReader::new(Cursor::new(buffer));
+        assert!(matches!(
+            reader.read_message::<Message>(),
+            Err(FrameError::InvalidJson(_))
+        ));
+        assert!(matches!(reader.read_message(), Ok(Message::Hello)));
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    challenge::{ChallengeAnswer, ChallengeType, ChallengeValue, ReportedChallengeResult},
+    public_player::PublicPlayer,
+};
+
+/// Why the server lowered the score of a player
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreReason {
+    /// The answer did not solve the challenge
+    BadResult,
+    /// The player answered after the end of the round
+    RoundExpired,
+}
+
+/// What happened during a game, in the order the server saw it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum GameEvent {
+    /// A server opens the lobby of a new game, a log may hold several games one after the other
+    GameStarted {
+        seed: u64,
+    },
+    PlayerJoined {
+        name: String,
+        stream_id: String,
+    },
+    RoundStarted {
+        round: usize,
+        game_type: String,
+    },
+    /// A challenge sent to a player, `difficulty` is set with adaptive difficulty
+    ChallengeIssued {
+        player: String,
+        challenge: ChallengeType,
+        difficulty: Option<f64>,
+    },
+    AnswerReceived {
+        player: String,
+        answer: ChallengeAnswer,
+        used_time: f64,
+        next_target: String,
+    },
+    AnswerVerified {
+        player: String,
+        passed: bool,
+    },
+    /// The player did not answer its challenge, `value` is `Timeout` or `Unreachable`
+    ChallengeMissed {
+        player: String,
+        value: ChallengeValue,
+    },
+    ScoreChanged {
+        player: String,
+        score: i32,
+        reason: ScoreReason,
+    },
+    RoundEnded {
+        round: usize,
+        challenge: String,
+        chain: Vec<ReportedChallengeResult>,
+        /// Difficulty of each challenge of the round with adaptive difficulty
+        difficulties: Vec<(String, f64)>,
+    },
+    GameEnded {
+        leader_board: Vec<PublicPlayer>,
+    },
+}
+
+/// A line of the event log: an event and the seconds elapsed since the start of the server
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EventRecord {
+    pub time: f64,
+    pub event: GameEvent,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_event_record_serialization() {
+        let record = EventRecord {
+            time: 1.5,
+            event: GameEvent::ScoreChanged {
+                player: "alice".to_string(),
+                score: -1,
+                reason: ScoreReason::BadResult,
+            },
+        };
+        let serialized = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"time":1.5,"event":{"ScoreChanged":{"player":"alice","score":-1,"reason":"BadResult"}}}"#
+        );
+    }
+
+    #[test]
+    fn test_challenge_issued_keeps_the_input() {
+        let line = json!({
+            "time": 0.0,
+            "event": {"ChallengeIssued": {
+                "player": "bob",
+                "challenge": {"MD5HashCash": {"complexity": 5, "message": "hello"}},
+                "difficulty": null
+            }}
+        });
+        let record: EventRecord = serde_json::from_value(line).unwrap();
+        match record.event {
+            GameEvent::ChallengeIssued {
+                challenge,
+                difficulty,
+                ..
+            } => {
+                assert_eq!(challenge.name, "MD5HashCash");
+                assert_eq!(challenge.input["complexity"], json!(5));
+                assert_eq!(difficulty, None);
+            }
+            other => panic!("Unexpected event {:?}", other),
+        }
+    }
+}
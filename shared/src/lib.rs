@@ -1,5 +1,8 @@
 pub mod challenge;
+pub mod codec;
+pub mod event;
 pub mod message;
 pub mod public_player;
+pub mod registry;
 pub mod result_type;
 pub mod subscribe;
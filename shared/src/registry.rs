@@ -0,0 +1,535 @@
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+use log::debug;
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use bloxorz::{challenge_generator::pick_level, models::Bloxorz};
+use hashcash::dto::{HashCashInput, MD5HashCash, SHA1HashCash, SHA256HashCash};
+use hashcash::solver::HashcashSolver;
+use monstrous_maze::challenge_generator::{
+    generate_default_maze_challenge, generate_difficulty_maze_challenge,
+};
+use monstrous_maze::models::MonstrousMaze;
+use nonogram::{challenge_generator::generate_default_nonogram, models::Nonogram};
+use recover_secret::challenge_generator::{
+    complexity_of_difficulty, generate_secret_challenge, SecretConfig,
+};
+use recover_secret::dictionary::Trie;
+use recover_secret::models::RecoverSecret;
+
+use crate::challenge::{Challenge, ChallengeAnswer, ChallengeType};
+
+/// Server settings a generator may use
+#[derive(Debug, Clone)]
+pub struct GeneratorContext {
+    /// Complexity of the generated RecoverSecret challenges
+    pub complexity: u8,
+    /// Difficulty between 0 (easiest) and 1 (hardest) chosen for the player, `None` keeps the
+    /// default random challenges
+    pub difficulty: Option<f64>,
+}
+
+impl GeneratorContext {
+    pub fn new(complexity: u8) -> GeneratorContext {
+        GeneratorContext {
+            complexity,
+            difficulty: None,
+        }
+    }
+
+    pub fn with_difficulty(self, difficulty: Option<f64>) -> GeneratorContext {
+        GeneratorContext { difficulty, ..self }
+    }
+}
+
+fn hashcash_input(context: &GeneratorContext, mut rng: &mut dyn RngCore) -> Value {
+    match context.difficulty {
+        Some(difficulty) => to_json(HashCashInput::with_difficulty(difficulty, &mut rng)),
+        None => to_json(HashCashInput::generate(&mut rng)),
+    }
+}
+
+/// Client resources a solver may use, a missing one falls back on the plain solve
+#[derive(Default, Clone, Copy)]
+pub struct SolverContext<'a> {
+    pub hashcash_solver: Option<&'a HashcashSolver>,
+    pub dictionary: Option<&'a Trie>,
+}
+
+pub trait ChallengeGenerator: Send + Sync {
+    /// JSON input of a new challenge, drawn from `rng` so that a seeded game is reproducible
+    fn generate(&self, context: &GeneratorContext, rng: &mut dyn RngCore) -> Value;
+}
+
+impl<F> ChallengeGenerator for F
+where
+    F: Fn(&GeneratorContext, &mut dyn RngCore) -> Value + Send + Sync,
+{
+    fn generate(&self, context: &GeneratorContext, rng: &mut dyn RngCore) -> Value {
+        self(context, rng)
+    }
+}
+
+pub trait ChallengeSolver: Send + Sync {
+    /// JSON output solving the JSON input
+    fn solve(&self, input: &Value, context: &SolverContext) -> Result<Value, RegistryError>;
+}
+
+pub trait ChallengeVerifier: Send + Sync {
+    /// Whether the JSON output solves the JSON input, malformed values never do
+    fn verify(&self, input: &Value, output: &Value) -> bool;
+}
+
+/// Solver and verifier of a [`Challenge`], reading its input and output from JSON
+pub struct TypedChallenge<C>(PhantomData<fn() -> C>);
+
+impl<C> Default for TypedChallenge<C> {
+    fn default() -> Self {
+        TypedChallenge(PhantomData)
+    }
+}
+
+impl<C> TypedChallenge<C>
+where
+    C: Challenge,
+    C::Input: DeserializeOwned,
+{
+    fn challenge(input: &Value) -> Result<C, RegistryError> {
+        serde_json::from_value(input.clone())
+            .map(C::new)
+            .map_err(|err| RegistryError::InvalidInput {
+                name: C::name(),
+                reason: err.to_string(),
+            })
+    }
+}
+
+impl<C> ChallengeSolver for TypedChallenge<C>
+where
+    C: Challenge,
+    C::Input: DeserializeOwned,
+    C::Output: Serialize,
+{
+    fn solve(&self, input: &Value, context: &SolverContext) -> Result<Value, RegistryError> {
+        let output = Self::challenge(input)?.solve_with(context);
+        Ok(to_json(output))
+    }
+}
+
+impl<C> ChallengeVerifier for TypedChallenge<C>
+where
+    C: Challenge,
+    C::Input: DeserializeOwned,
+    C::Output: DeserializeOwned,
+{
+    fn verify(&self, input: &Value, output: &Value) -> bool {
+        match (
+            Self::challenge(input),
+            serde_json::from_value(output.clone()),
+        ) {
+            (Ok(challenge), Ok(output)) => challenge.verify(output),
+            _ => false,
+        }
+    }
+}
+
+pub struct RegisteredChallenge {
+    /// Name of the challenge in the messages, e.g. `MD5HashCash`
+    pub name: String,
+    /// Value of the server `--game-type` option, e.g. `hash-cash`
+    pub game_type: String,
+    pub generator: Box<dyn ChallengeGenerator>,
+    pub solver: Box<dyn ChallengeSolver>,
+    pub verifier: Box<dyn ChallengeVerifier>,
+    /// Inputs are drawn from a fixed set and come back from game to game, their answers are
+    /// worth keeping between two games
+    pub is_recurring: bool,
+}
+
+impl RegisteredChallenge {
+    /// Registration of a [`Challenge`] implementation, solved and verified by [`TypedChallenge`]
+    pub fn typed<C>(game_type: &str, generator: impl ChallengeGenerator + 'static) -> Self
+    where
+        C: Challenge + 'static,
+        C::Input: DeserializeOwned,
+        C::Output: Serialize + DeserializeOwned,
+    {
+        RegisteredChallenge {
+            name: C::name(),
+            game_type: game_type.to_string(),
+            generator: Box::new(generator),
+            solver: Box::new(TypedChallenge::<C>::default()),
+            verifier: Box::new(TypedChallenge::<C>::default()),
+            is_recurring: false,
+        }
+    }
+
+    pub fn recurring(self) -> Self {
+        RegisteredChallenge {
+            is_recurring: true,
+            ..self
+        }
+    }
+}
+
+/// Challenges known by the server and the client, in registration order
+#[derive(Default)]
+pub struct ChallengeRegistry {
+    challenges: Vec<RegisteredChallenge>,
+}
+
+impl ChallengeRegistry {
+    pub fn new() -> ChallengeRegistry {
+        ChallengeRegistry::default()
+    }
+
+    /// Registry of every challenge crate of the workspace
+    pub fn with_builtin_challenges() -> ChallengeRegistry {
+        let mut registry = ChallengeRegistry::new();
+        let challenges = [
+            RegisteredChallenge::typed::<MD5HashCash>("hash-cash", hashcash_input),
+            RegisteredChallenge::typed::<SHA1HashCash>("sha1-hash-cash", hashcash_input),
+            RegisteredChallenge::typed::<SHA256HashCash>("sha256-hash-cash", hashcash_input),
+            RegisteredChallenge::typed::<RecoverSecret>(
+                "recover-secret",
+                |context: &GeneratorContext, mut rng: &mut dyn RngCore| {
+                    let complexity = context
+                        .difficulty
+                        .map_or(context.complexity, complexity_of_difficulty);
+                    let config = SecretConfig::from_complexity(complexity);
+                    let challenge = generate_secret_challenge(&config, &mut rng);
+                    debug!("Hidden sentence: {:?}", challenge.secret_sentence);
+                    to_json(challenge.input)
+                },
+            ),
+            RegisteredChallenge::typed::<MonstrousMaze>(
+                "monstrous-maze",
+                |context: &GeneratorContext, mut rng: &mut dyn RngCore| match context.difficulty {
+                    Some(difficulty) => {
+                        to_json(generate_difficulty_maze_challenge(difficulty, &mut rng))
+                    }
+                    None => to_json(generate_default_maze_challenge(&mut rng)),
+                },
+            ),
+            RegisteredChallenge::typed::<Nonogram>(
+                "nonogram",
+                |_: &GeneratorContext, mut rng: &mut dyn RngCore| {
+                    to_json(generate_default_nonogram(&mut rng))
+                },
+            ),
+            RegisteredChallenge::typed::<Bloxorz>(
+                "bloxorz",
+                |_: &GeneratorContext, mut rng: &mut dyn RngCore| to_json(pick_level(&mut rng)),
+            )
+            .recurring(),
+        ];
+        for challenge in challenges {
+            registry
+                .register(challenge)
+                .expect("The builtin challenges have distinct names");
+        }
+        registry
+    }
+
+    /// Adds a challenge, its name and game type must not be registered yet
+    pub fn register(&mut self, challenge: RegisteredChallenge) -> Result<(), RegistryError> {
+        if self.get(&challenge.name).is_some() {
+            return Err(RegistryError::AlreadyRegistered(challenge.name));
+        }
+        if self.get_by_game_type(&challenge.game_type).is_some() {
+            return Err(RegistryError::AlreadyRegistered(challenge.game_type));
+        }
+        self.challenges.push(challenge);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegisteredChallenge> {
+        self.challenges
+            .iter()
+            .find(|challenge| challenge.name == name)
+    }
+
+    pub fn get_by_game_type(&self, game_type: &str) -> Option<&RegisteredChallenge> {
+        self.challenges
+            .iter()
+            .find(|challenge| challenge.game_type == game_type)
+    }
+
+    /// Whether the inputs of the challenge come back, `false` for an unknown challenge
+    pub fn is_recurring(&self, name: &str) -> bool {
+        self.get(name)
+            .is_some_and(|challenge| challenge.is_recurring)
+    }
+
+    pub fn game_types(&self) -> Vec<String> {
+        self.challenges
+            .iter()
+            .map(|challenge| challenge.game_type.clone())
+            .collect()
+    }
+
+    pub fn generate(
+        &self,
+        game_type: &str,
+        context: &GeneratorContext,
+        rng: &mut dyn RngCore,
+    ) -> Result<ChallengeType, RegistryError> {
+        let challenge = self
+            .get_by_game_type(game_type)
+            .ok_or_else(|| RegistryError::UnknownGameType(game_type.to_string()))?;
+        Ok(ChallengeType {
+            name: challenge.name.clone(),
+            input: challenge.generator.generate(context, rng),
+        })
+    }
+
+    pub fn solve(
+        &self,
+        challenge: &ChallengeType,
+        context: &SolverContext,
+    ) -> Result<ChallengeAnswer, RegistryError> {
+        let registered = self
+            .get(&challenge.name)
+            .ok_or_else(|| RegistryError::UnknownChallenge(challenge.name.clone()))?;
+        Ok(ChallengeAnswer {
+            name: challenge.name.clone(),
+            output: registered.solver.solve(&challenge.input, context)?,
+        })
+    }
+
+    /// Whether the answer solves the challenge, an answer to another challenge never does
+    pub fn verify(&self, challenge: &ChallengeType, answer: &ChallengeAnswer) -> bool {
+        answer.name == challenge.name
+            && self.get(&challenge.name).is_some_and(|registered| {
+                registered.verifier.verify(&challenge.input, &answer.output)
+            })
+    }
+}
+
+/// Registry of the builtin challenges, shared by the whole process
+pub fn registry() -> &'static ChallengeRegistry {
+    static REGISTRY: OnceLock<ChallengeRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(ChallengeRegistry::with_builtin_challenges)
+}
+
+fn to_json<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).expect("Could not serialize challenge value")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    UnknownChallenge(String),
+    UnknownGameType(String),
+    /// The name or the game type is already used by another challenge
+    AlreadyRegistered(String),
+    InvalidInput {
+        name: String,
+        reason: String,
+    },
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::UnknownChallenge(name) => write!(f, "unknown challenge {:?}", name),
+            RegistryError::UnknownGameType(game_type) => {
+                write!(f, "unknown game type {:?}", game_type)
+            }
+            RegistryError::AlreadyRegistered(name) => write!(f, "{:?} is already registered", name),
+            RegistryError::InvalidInput { name, reason } => {
+                write!(f, "invalid input of challenge {}: {}", name, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{thread_rng, SeedableRng};
+    use serde_json::json;
+
+    use super::*;
+
+    /// Challenge defined outside of the builtin crates: the answer is the reversed word
+    struct ReverseSolver;
+
+    impl ChallengeSolver for ReverseSolver {
+        fn solve(&self, input: &Value, _context: &SolverContext) -> Result<Value, RegistryError> {
+            match input.as_str() {
+                Some(word) => Ok(json!(word.chars().rev().collect::<String>())),
+                None => Err(RegistryError::InvalidInput {
+                    name: "Reverse".to_string(),
+                    reason: "not a string".to_string(),
+                }),
+            }
+        }
+    }
+
+    struct ReverseVerifier;
+
+    impl ChallengeVerifier for ReverseVerifier {
+        fn verify(&self, input: &Value, output: &Value) -> bool {
+            ReverseSolver
+                .solve(input, &SolverContext::default())
+                .is_ok_and(|expected| expected == *output)
+        }
+    }
+
+    fn reverse_challenge() -> RegisteredChallenge {
+        RegisteredChallenge {
+            name: "Reverse".to_string(),
+            game_type: "reverse".to_string(),
+            generator: Box::new(|_: &GeneratorContext, _: &mut dyn RngCore| json!("stressed")),
+            solver: Box::new(ReverseSolver),
+            verifier: Box::new(ReverseVerifier),
+            is_recurring: false,
+        }
+    }
+
+    fn context() -> GeneratorContext {
+        GeneratorContext::new(6)
+    }
+
+    #[test]
+    fn test_register_custom_challenge() {
+        let mut registry = ChallengeRegistry::new();
+        registry.register(reverse_challenge()).unwrap();
+        assert_eq!(registry.game_types(), vec!["reverse"]);
+        let challenge = registry
+            .generate("reverse", &context(), &mut thread_rng())
+            .unwrap();
+        assert_eq!(challenge.name, "Reverse");
+        let answer = registry
+            .solve(&challenge, &SolverContext::default())
+            .unwrap();
+        assert_eq!(answer.output, json!("desserts"));
+        assert!(registry.verify(&challenge, &answer));
+        assert_eq!(
+            registry.register(reverse_challenge()),
+            Err(RegistryError::AlreadyRegistered("Reverse".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unknown_challenge() {
+        let registry = ChallengeRegistry::new();
+        assert_eq!(
+            registry
+                .generate("reverse", &context(), &mut thread_rng())
+                .unwrap_err(),
+            RegistryError::UnknownGameType("reverse".to_string())
+        );
+        let challenge = ChallengeType {
+            name: "Reverse".to_string(),
+            input: json!("stressed"),
+        };
+        assert_eq!(
+            registry
+                .solve(&challenge, &SolverContext::default())
+                .unwrap_err(),
+            RegistryError::UnknownChallenge("Reverse".to_string())
+        );
+        let answer = ChallengeAnswer {
+            name: "Reverse".to_string(),
+            output: json!("desserts"),
+        };
+        assert!(!registry.verify(&challenge, &answer));
+    }
+
+    #[test]
+    fn test_builtin_challenges() {
+        let registry = registry();
+        assert_eq!(
+            registry.game_types(),
+            vec![
+                "hash-cash",
+                "sha1-hash-cash",
+                "sha256-hash-cash",
+                "recover-secret",
+                "monstrous-maze",
+                "nonogram",
+                "bloxorz"
+            ]
+        );
+        for game_type in registry.game_types() {
+            let challenge = registry
+                .generate(&game_type, &context(), &mut thread_rng())
+                .unwrap();
+            assert_eq!(
+                challenge.name,
+                registry.get_by_game_type(&game_type).unwrap().name
+            );
+            if game_type.ends_with("hash-cash") {
+                // Generated hash cash complexities are too slow for a test
+                continue;
+            }
+            let answer = registry
+                .solve(&challenge, &SolverContext::default())
+                .unwrap();
+            assert!(registry.verify(&challenge, &answer), "{}", game_type);
+        }
+        assert!(registry.is_recurring("Bloxorz"));
+        assert!(!registry.is_recurring("MonstrousMaze"));
+        assert!(!registry.is_recurring("Unknown"));
+    }
+
+    #[test]
+    fn test_generate_with_difficulty() {
+        let registry = registry();
+        let easiest = context().with_difficulty(Some(0.0));
+        for game_type in registry.game_types() {
+            let challenge = registry
+                .generate(&game_type, &easiest, &mut thread_rng())
+                .unwrap();
+            let answer = registry
+                .solve(&challenge, &SolverContext::default())
+                .unwrap();
+            assert!(registry.verify(&challenge, &answer), "{}", game_type);
+        }
+        let hardest = context().with_difficulty(Some(1.0));
+        let challenge = registry
+            .generate("hash-cash", &hardest, &mut thread_rng())
+            .unwrap();
+        assert_eq!(challenge.input["complexity"], json!(23));
+    }
+
+    #[test]
+    fn test_seeded_generation_is_reproducible() {
+        let registry = registry();
+        let generate_all = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            registry
+                .game_types()
+                .iter()
+                .map(|game_type| registry.generate(game_type, &context(), &mut rng).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(generate_all(25), generate_all(25));
+        assert_ne!(generate_all(25), generate_all(26));
+    }
+
+    #[test]
+    fn test_solve_invalid_input() {
+        let challenge = ChallengeType {
+            name: "MonstrousMaze".to_string(),
+            input: json!({"grid": 42}),
+        };
+        assert!(matches!(
+            registry().solve(&challenge, &SolverContext::default()),
+            Err(RegistryError::InvalidInput { .. })
+        ));
+        let answer = ChallengeAnswer {
+            name: "MonstrousMaze".to_string(),
+            output: json!({"path": ""}),
+        };
+        assert!(!registry().verify(&challenge, &answer));
+    }
+}
@@ -18,3 +18,9 @@ pub fn read_mazes_file_macro() -> String {
     let str = include_str!("../../data/mazes.txt");
     str.to_string()
 }
+
+/// Word list of `data/liste-mots-alphabetique.txt`, the file is encoded in Latin-1
+pub fn read_word_list_file_macro() -> String {
+    let bytes = include_bytes!("../../data/liste-mots-alphabetique.txt");
+    bytes.iter().map(|byte| *byte as char).collect()
+}
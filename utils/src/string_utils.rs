@@ -307,16 +307,13 @@ pub fn generate_random_tuple(length: &usize) -> Vec<char> {
     random_string
 }
 
-pub fn is_sequence_valid(string: &String, sequence: &Vec<char>) -> bool {
-    let mut string_check = string.clone();
-    for char in sequence {
-        if !is_present(&string_check, char) {
-            return false;
-        }
-        string_check = get_string_after_first_occurrence(&string_check, char);
-    }
-
-    true
+/// Whether the characters of `sequence` appear in `string` in the same order, walks chars so
+/// accented letters are handled
+pub fn is_sequence_valid(string: &str, sequence: &[char]) -> bool {
+    let mut letters = string.chars();
+    sequence
+        .iter()
+        .all(|character| letters.any(|letter| letter == *character))
 }
 
 #[cfg(test)]
@@ -327,7 +324,7 @@ mod tests {
         get_string_after_vec_sequence, get_string_after_vec_sequence_inclusive,
         get_string_before_first_occurrence, get_string_before_last_occurrence,
         get_string_before_n_occurrence, get_string_before_sequence, get_string_before_vec_sequence,
-        get_string_before_vec_sequence_inclusive, is_present,
+        get_string_before_vec_sequence_inclusive, is_present, is_sequence_valid,
     };
 
     #[test]
@@ -526,4 +523,13 @@ mod tests {
         let new_string = add_spaces_in_sequence(&"hello world", &6);
         assert_eq!(new_string, "h e l l o w o rld".to_string());
     }
+
+    #[test]
+    fn test_is_sequence_valid() {
+        let string = "l'été était chaud".to_string();
+        assert!(is_sequence_valid(&string, &['l', 'é', 'é', 'a', 'c']));
+        assert!(is_sequence_valid(&string, &['\'', ' ', ' ', 'd']));
+        assert!(!is_sequence_valid(&string, &['é', 'l', 'é', 'é']));
+        assert!(!is_sequence_valid(&string, &['c', 'h', 'a', 'ô']));
+    }
 }